@@ -13,29 +13,83 @@ pub const LIQUIDITY_SEED: &[u8] = b"b"; // liquidity
 pub const LENDING_AUTHORITY_SEED: &[u8] = b"d"; // lending_authority
 
 #[constant]
-pub const LENDING_TOKEN_SEED: &[u8] = b"e"; // lending_token
+pub const LENDING_POSITION_SEED: &[u8] = b"k"; // lending_position
 
 #[constant]
-pub const BORROW_TOKEN_SEED: &[u8] = b"f"; // borrow_token
+pub const BORROW_POSITION_SEED: &[u8] = b"l"; // borrow_position
 
 #[constant]
-pub const BORROWER_AUTHORITY_SEED: &[u8] = b"g"; // borrower_authority
+pub const CL_POOL_SEED: &[u8] = b"m"; // concentrated liquidity pool
 
 #[constant]
-pub const COLLATERAL_TOKEN_SEED: &[u8] = b"h"; // collateral_token
+pub const CL_TICK_SEED: &[u8] = b"n"; // concentrated liquidity tick
 
 #[constant]
-pub const LENDER_LENDING_BLOCK_HEIGHT_TOKEN_SEED: &[u8] = b"i"; // lending_height_token
+pub const CL_POSITION_SEED: &[u8] = b"o"; // concentrated liquidity position
+
 
 #[constant]
-pub const BORROWER_BORROW_BLOCK_HEIGHT_TOKEN_SEED: &[u8] = b"j"; // borrow_height_token   
+pub const PERCENT_BASE: u64 = 10000; // 100%
 
+#[constant]
+pub const MAX_LIQUIDITY_FEE: u64 = 1000; // 10% per swap
 
 #[constant]
-pub const PERCENT_BASE: u64 = 10000; // 100%
+pub const MAX_FEE_TIERS: usize = 8; // fee tiers an AMM can offer
+
+#[constant]
+pub const DEFAULT_FEE_TIERS: [u16; 4] = [1, 5, 30, 100]; // 0.01%, 0.05%, 0.3%, 1%
+
+#[constant]
+pub const MAX_AMP: u64 = 10_000; // StableSwap amplification coefficient
+
+#[constant]
+pub const SLOTS_PER_YEAR: u64 = 78_840_000; // 400ms slots
+
+#[constant]
+pub const MAX_BORROW_RATE: u64 = 1_000_000; // 10000% per year
+
+#[constant]
+pub const WAD: u128 = 1_000_000_000_000_000_000; // 1.0 for indexes
+
+#[constant]
+pub const CLOSE_FACTOR: u64 = 5000; // 50% of the debt per liquidation
+
+#[constant]
+pub const MAX_LIQUIDATION_BONUS: u64 = 2000; // 20%
+
+#[constant]
+pub const LIQUIDATION_SWAP_TOLERANCE: u64 = 500; // 5% below the TWAP value for seized collateral sold through the pool
+
+#[constant]
+pub const FLASH_LOAN_FEE: u64 = 9; // 0.09% of the flash loan, paid to lenders
+
+#[constant]
+pub const OBSERVATION_CAPACITY: usize = 16; // price observations kept per pool
+
+#[constant]
+pub const DEFAULT_TWAP_WINDOW: u64 = 150; // ~1 minute of slots
+
+#[constant]
+pub const MAX_OBSERVATION_INTERVAL: u64 = 9000; // ~1 hour of slots between price observations
+
+#[constant]
+pub const MAX_TWAP_WINDOW: u64 = (OBSERVATION_CAPACITY as u64 - 1) * MAX_OBSERVATION_INTERVAL; // longest window the observations can cover
+
+#[constant]
+pub const DYNAMIC_FEE_SENSITIVITY: u64 = 5000; // half of the price deviation from the TWAP is added to the fee
+
+#[constant]
+pub const MIN_TICK: i32 = -443636; // sqrt price 2^-32
+
+#[constant]
+pub const MAX_TICK: i32 = 443636; // sqrt price 2^32
+
+#[constant]
+pub const MIN_TICK_SPACING: u16 = 109; // smallest spacing whose bitmap covers MIN_TICK..MAX_TICK
 
 #[constant]
-pub const BASE_INTEREST_RATE: u64 = 5; // 0.05%
+pub const MAX_TICK_SPACING: u16 = 1000;
 
 #[constant]
-pub const MIN_COLLATERAL_RATIO: u64 = 10000; // 100%
\ No newline at end of file
+pub const TICK_BITMAP_WORDS: usize = 128; // 8192 compressed ticks per pool: [-4096, 4095] * tick_spacing
@@ -0,0 +1,305 @@
+use anchor_lang::prelude::*;
+use crate::math::U256;
+
+/// Bonding curve of a pool
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum CurveType {
+    /// Uniswap v2 style x * y = k
+    #[default]
+    ConstantProduct,
+    /// Curve style StableSwap invariant for correlated pairs, see `amp`
+    StableSwap,
+}
+
+/// Newton iterations before giving up on convergence
+const MAX_ITERATIONS: usize = 255;
+
+/// Raw swap output (before the liquidity fee) for `input_amount`, rounded down
+pub fn swap_output(
+    curve_type: CurveType,
+    amp: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    input_amount: u64,
+) -> Result<u128> {
+    let invariant = swap_invariant(curve_type, amp, reserve_in, reserve_out)?;
+    swap_output_with_invariant(curve_type, amp, invariant, reserve_in, reserve_out, input_amount)
+}
+
+/// Invariant of the reserves for `swap_output_with_invariant`: D on StableSwap, unused on constant product
+pub fn swap_invariant(curve_type: CurveType, amp: u64, reserve_in: u64, reserve_out: u64) -> Result<U256> {
+    match curve_type {
+        CurveType::ConstantProduct => Ok(U256::zero()),
+        CurveType::StableSwap => compute_d(amp, reserve_in, reserve_out),
+    }
+}
+
+/// `swap_output` with the invariant of the reserves computed once by `swap_invariant`,
+/// for pricing many input amounts against the same reserves
+pub fn swap_output_with_invariant(
+    curve_type: CurveType,
+    amp: u64,
+    invariant: U256,
+    reserve_in: u64,
+    reserve_out: u64,
+    input_amount: u64,
+) -> Result<u128> {
+    match curve_type {
+        CurveType::ConstantProduct => {
+            let new_reserve_in = (reserve_in as u128)
+                .checked_add(input_amount as u128)
+                .ok_or(CurveError::CalculationError)?;
+            Ok((reserve_out as u128)
+                .checked_mul(input_amount as u128)
+                .ok_or(CurveError::CalculationError)?
+                .checked_div(new_reserve_in)
+                .ok_or(CurveError::CalculationError)?)
+        }
+        CurveType::StableSwap => {
+            let new_reserve_in = U256::from(reserve_in) + U256::from(input_amount);
+            let new_reserve_out = compute_y(amp, new_reserve_in, invariant)?;
+            // Keep one unit in the pool to absorb the rounding of the Newton iterations
+            Ok(U256::from(reserve_out)
+                .saturating_sub(new_reserve_out)
+                .saturating_sub(U256::one())
+                .as_u128())
+        }
+    }
+}
+
+/// Raw swap input needed to take `raw_output` (before the liquidity fee) out of the pool, rounded up
+pub fn swap_input(
+    curve_type: CurveType,
+    amp: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    raw_output: u128,
+) -> Result<u128> {
+    require!(raw_output < reserve_out as u128, CurveError::InsufficientLiquidity);
+    match curve_type {
+        CurveType::ConstantProduct => {
+            // (reserve_in + input) * (reserve_out - raw_output) >= reserve_in * reserve_out
+            Ok((reserve_in as u128)
+                .checked_mul(raw_output)
+                .ok_or(CurveError::CalculationError)?
+                .div_ceil(reserve_out as u128 - raw_output))
+        }
+        CurveType::StableSwap => {
+            let d = compute_d(amp, reserve_in, reserve_out)?;
+            let new_reserve_out = U256::from(reserve_out) - U256::from(raw_output);
+            let new_reserve_in = compute_y(amp, new_reserve_out, d)?;
+            let input = new_reserve_in
+                .saturating_sub(U256::from(reserve_in))
+                + U256::one();
+            require!(input <= U256::from(u128::MAX), CurveError::CalculationError);
+            Ok(input.as_u128())
+        }
+    }
+}
+
+/// Liquidity value of the amounts: sqrt(a * b) on the constant product curve, D on StableSwap
+pub fn liquidity(curve_type: CurveType, amp: u64, amount_a: u64, amount_b: u64) -> Result<u64> {
+    let liquidity = match curve_type {
+        CurveType::ConstantProduct => {
+            let product = U256::from(amount_a) * U256::from(amount_b);
+            product.integer_sqrt()
+        }
+        CurveType::StableSwap => compute_d(amp, amount_a, amount_b)?,
+    };
+    require!(liquidity <= U256::from(u64::MAX), CurveError::CalculationError);
+    Ok(liquidity.as_u64())
+}
+
+/// Whether a trade moving the reserves from (old_x, old_y) to (new_x, new_y) kept the invariant from decreasing
+pub fn invariant_holds(
+    curve_type: CurveType,
+    amp: u64,
+    old_x: u64,
+    old_y: u64,
+    new_x: u64,
+    new_y: u64,
+) -> Result<bool> {
+    match curve_type {
+        CurveType::ConstantProduct => Ok(
+            (new_x as u128) * (new_y as u128) >= (old_x as u128) * (old_y as u128)
+        ),
+        CurveType::StableSwap => Ok(
+            compute_d(amp, new_x, new_y)? >= compute_d(amp, old_x, old_y)?
+        ),
+    }
+}
+
+/// Marginal price of token X in token Y (-dy/dx) at reserves (x, y), as Q64.64
+pub fn spot_price(curve_type: CurveType, amp: u64, x: u64, y: u64) -> Result<u128> {
+    require!(x > 0 && y > 0, CurveError::CalculationError);
+    let price = match curve_type {
+        CurveType::ConstantProduct => (U256::from(y) << 64) / U256::from(x),
+        CurveType::StableSwap => {
+            // Implicit derivative of A*n^n*(x+y) + D = A*n^n*D + D^3/(4xy):
+            // -dy/dx = (Ann + D^3/(4x^2y)) / (Ann + D^3/(4xy^2)), multiplied through by xy
+            let d = compute_d(amp, x, y)?;
+            let ann = ann(amp);
+            let d_p = d * d / (U256::from(x) * 2) * d / (U256::from(y) * 2);
+            let xy = U256::from(x) * U256::from(y);
+            let numerator = ann * xy + d_p * U256::from(y);
+            let denominator = ann * xy + d_p * U256::from(x);
+            (numerator << 64) / denominator
+        }
+    };
+    require!(price <= U256::from(u128::MAX), CurveError::CalculationError);
+    Ok(price.as_u128())
+}
+
+/// A * n^n for two tokens
+fn ann(amp: u64) -> U256 {
+    U256::from(amp) * 4
+}
+
+/// StableSwap invariant D for reserves (x, y), solved with Newton's method
+fn compute_d(amp: u64, x: u64, y: u64) -> Result<U256> {
+    let sum = U256::from(x) + U256::from(y);
+    if x == 0 || y == 0 {
+        return Ok(sum);
+    }
+    let ann = ann(amp);
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // d_p = D^3 / (4xy), divided once so that Newton does not stall on very imbalanced reserves
+        let d_p = d * d * d / (U256::from(x) * U256::from(y) * 4);
+        let d_prev = d;
+        d = (ann * sum + d_p * 2) * d / ((ann - 1) * d + d_p * 3);
+        if d.abs_diff(d_prev) <= U256::one() {
+            return Ok(d);
+        }
+    }
+    err!(CurveError::NoConvergence)
+}
+
+/// Reserve of the other token that keeps the invariant at `d` when one reserve is `x`
+fn compute_y(amp: u64, x: U256, d: U256) -> Result<U256> {
+    require!(!x.is_zero(), CurveError::CalculationError);
+    let ann = ann(amp);
+    // y^2 + (x + D/Ann - D) * y = D^3 / (4 * x * Ann)
+    let c = d * d / (x * 2) * d / (ann * 2);
+    let b = x + d / ann;
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        y = (y * y + c) / (y * 2 + b - d);
+        if y.abs_diff(y_prev) <= U256::one() {
+            return Ok(y);
+        }
+    }
+    err!(CurveError::NoConvergence)
+}
+
+#[error_code]
+pub enum CurveError {
+    #[msg("Calculation error")]
+    CalculationError,
+    #[msg("Insufficient pool liquidity")]
+    InsufficientLiquidity,
+    #[msg("Curve invariant did not converge")]
+    NoConvergence,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMPS: [u64; 4] = [1, 10, 100, 10_000];
+    const RESERVES: [(u64, u64); 5] = [
+        (1_000_000, 1_000_000),
+        (1_000_000_000, 3_000_000_000),
+        (5_000_000_000_000, 7_000),
+        (1_000, u64::MAX),
+        (u64::MAX / 4, u64::MAX / 2),
+    ];
+
+    #[test]
+    fn compute_d_of_balanced_reserves_is_their_sum() {
+        for amp in AMPS {
+            assert_eq!(compute_d(amp, 1_000_000, 1_000_000).unwrap(), U256::from(2_000_000u64));
+        }
+    }
+
+    #[test]
+    fn compute_y_inverts_compute_d() {
+        for amp in AMPS {
+            for (x, y) in RESERVES {
+                let d = compute_d(amp, x, y).unwrap();
+                assert!(d <= U256::from(x) + U256::from(y));
+                let solved = compute_y(amp, U256::from(x), d).unwrap();
+                // Rounding of the Newton iterations stays within a millionth of y
+                let tolerance = U256::from(y / 1_000_000 + 2);
+                assert!(solved.abs_diff(U256::from(y)) <= tolerance, "amp {amp} x {x} y {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn stable_swap_output_sits_between_constant_product_and_constant_sum() {
+        let (reserve, input) = (1_000_000_000u64, 10_000_000u64);
+        let constant_product = swap_output(CurveType::ConstantProduct, 0, reserve, reserve, input).unwrap();
+        let mut previous = constant_product;
+        for amp in AMPS {
+            let output = swap_output(CurveType::StableSwap, amp, reserve, reserve, input).unwrap();
+            // A higher amplification flattens the curve towards 1:1
+            assert!(output >= previous, "amp {amp}");
+            assert!(output < input as u128);
+            previous = output;
+        }
+    }
+
+    #[test]
+    fn swap_output_is_monotonic_in_the_input() {
+        for curve_type in [CurveType::ConstantProduct, CurveType::StableSwap] {
+            for amp in AMPS {
+                let mut previous = 0;
+                for input in [1u64, 1_000, 100_000, 10_000_000, 1_000_000_000] {
+                    let output = swap_output(curve_type, amp, 1_000_000_000, 2_000_000_000, input).unwrap();
+                    assert!(output >= previous);
+                    previous = output;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_swap_does_not_return_more_than_the_input() {
+        for curve_type in [CurveType::ConstantProduct, CurveType::StableSwap] {
+            for amp in AMPS {
+                let (x, y, input) = (1_000_000_000u64, 1_500_000_000u64, 50_000_000u64);
+                let output = swap_output(curve_type, amp, x, y, input).unwrap() as u64;
+                let back = swap_output(curve_type, amp, y - output, x + input, output).unwrap();
+                assert!(back <= input as u128, "{curve_type:?} amp {amp}");
+            }
+        }
+    }
+
+    #[test]
+    fn swap_input_covers_the_requested_output() {
+        for curve_type in [CurveType::ConstantProduct, CurveType::StableSwap] {
+            for amp in AMPS {
+                let (x, y, raw_output) = (1_000_000_000u64, 1_500_000_000u64, 50_000_000u128);
+                let input = swap_input(curve_type, amp, x, y, raw_output).unwrap() as u64;
+                assert!(swap_output(curve_type, amp, x, y, input).unwrap() >= raw_output);
+            }
+        }
+    }
+
+    #[test]
+    fn invariant_holds_after_a_swap() {
+        for curve_type in [CurveType::ConstantProduct, CurveType::StableSwap] {
+            for amp in AMPS {
+                for (x, y) in RESERVES {
+                    let input = x / 10;
+                    let output = swap_output(curve_type, amp, x, y, input).unwrap() as u64;
+                    assert!(invariant_holds(curve_type, amp, x, y, x + input, y - output).unwrap());
+                    // Taking tokens out without paying anything in must break it
+                    assert!(!invariant_holds(curve_type, amp, x, y, x, y - y / 1_000).unwrap());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use anchor_lang::prelude::*;
+use crate::curve::CurveType;
+
+// AMM administration
+
+#[event]
+pub struct AmmCreated {
+    pub amm: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct FeesUpdated {
+    pub amm: Pubkey,
+    pub fee_tiers: Vec<u16>,
+    pub protocol_fee_percentage: u16,
+}
+
+#[event]
+pub struct AdminProposed {
+    pub amm: Pubkey,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminAccepted {
+    pub amm: Pubkey,
+    pub previous_admin: Pubkey,
+    pub admin: Pubkey,
+}
+
+// Markets
+
+#[event]
+pub struct MarketCreated {
+    pub pool: Pubkey,
+    pub amm: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub liquidity_fee: u16,
+    pub curve_type: CurveType,
+    pub amp: u64,
+    pub creator: Pubkey,
+}
+
+#[event]
+pub struct TwapWindowUpdated {
+    pub pool: Pubkey,
+    pub twap_window: u64,
+}
+
+#[event]
+pub struct DynamicFeeUpdated {
+    pub pool: Pubkey,
+    pub enabled: bool,
+    pub min_fee: u16,
+    pub max_fee: u16,
+}
+
+#[event]
+pub struct RiskParamsUpdated {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub max_ltv: u64,
+    pub liquidation_threshold: u64,
+    pub liquidation_bonus: u64,
+}
+
+// Swaps and liquidity, reserves are the ones after the instruction
+
+#[event]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub trader: Pubkey,
+    pub mint_in: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub protocol_fee: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub liquidity: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub liquidity: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+pub struct ProtocolFeesCollected {
+    pub pool: Pubkey,
+    pub admin: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+// Concentrated liquidity
+
+#[event]
+pub struct ClPoolCreated {
+    pub cl_pool: Pubkey,
+    pub amm: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub liquidity_fee: u16,
+    pub tick_spacing: u16,
+    pub sqrt_price: u128,
+    pub tick_current: i32,
+}
+
+#[event]
+pub struct ClPositionOpened {
+    pub cl_pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+}
+
+#[event]
+pub struct ClPositionClosed {
+    pub cl_pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+}
+
+#[event]
+pub struct ClLiquidityAdded {
+    pub cl_pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub liquidity: u128,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub pool_liquidity: u128,
+}
+
+#[event]
+pub struct ClLiquidityRemoved {
+    pub cl_pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub liquidity: u128,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub pool_liquidity: u128,
+}
+
+#[event]
+pub struct ClFeesCollected {
+    pub cl_pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct ClSwapEvent {
+    pub cl_pool: Pubkey,
+    pub trader: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub sqrt_price: u128,
+    pub tick_current: i32,
+    pub liquidity: u128,
+}
+
+// Lending, index values are the ones after the instruction
+
+#[event]
+pub struct InterestAccrued {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub interest: u64,
+    pub borrow_index: u128,
+    pub supply_index: u128,
+    pub total_borrows: u64,
+    pub total_deposits: u64,
+}
+
+#[event]
+pub struct Lent {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub lender: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub supply_index: u128,
+    pub total_deposits: u64,
+}
+
+#[event]
+pub struct Redeemed {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub lender: Pubkey,
+    pub redeemed: u64,
+    pub amount: u64,
+    pub collateral_amount: u64,
+    pub supply_index: u128,
+    pub total_deposits: u64,
+}
+
+#[event]
+pub struct Borrowed {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub debt: u64,
+    pub borrow_index: u128,
+    pub total_borrows: u64,
+}
+
+#[event]
+pub struct CollateralDeposited {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub collateral: u64,
+}
+
+#[event]
+pub struct CollateralWithdrawn {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub collateral: u64,
+}
+
+#[event]
+pub struct Repaid {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub debt: u64,
+    pub collateral_returned: u64,
+    pub borrow_index: u128,
+    pub total_borrows: u64,
+}
+
+#[event]
+pub struct Liquidated {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub borrower: Pubkey,
+    pub liquidator: Pubkey,
+    pub repay_amount: u64,
+    pub collateral_seized: u64,
+    pub debt: u64,
+    pub bad_debt: u64,
+    pub borrow_index: u128,
+    pub total_borrows: u64,
+}
+
+#[event]
+pub struct FlashBorrowed {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub borrower: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FlashRepaid {
+    pub pool: Pubkey,
+    pub lend_mint: Pubkey,
+    pub repayer: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub supply_index: u128,
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use crate::{events::AdminAccepted, state::Amm};
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+        has_one = pending_admin @ AdminError::NotPendingAdmin,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    pub pending_admin: Signer<'info>,
+}
+
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let amm = &mut ctx.accounts.amm;
+    let previous_admin = amm.admin;
+    amm.admin = amm.pending_admin;
+    amm.pending_admin = Pubkey::default();
+
+    emit!(AdminAccepted {
+        amm: amm.key(),
+        previous_admin,
+        admin: amm.admin,
+    });
+
+    Ok(())
+}
+
+#[error_code]
+pub enum AdminError {
+    #[msg("Signer is not the pending admin")]
+    NotPendingAdmin,
+}
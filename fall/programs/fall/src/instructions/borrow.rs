@@ -3,23 +3,30 @@ use anchor_spl::{
     token::{self, Mint, Token, TokenAccount, Transfer},
     associated_token::AssociatedToken,
 };
-use crate::constants::{ LENDING_AUTHORITY_SEED, BORROWER_AUTHORITY_SEED, BORROW_TOKEN_SEED, COLLATERAL_TOKEN_SEED, BORROWER_BORROW_BLOCK_HEIGHT_TOKEN_SEED };
-use crate::state::Pool;
-use crate::instructions::utils::mint_and_freeze_token;
+use crate::constants::{ LENDING_AUTHORITY_SEED, BORROW_POSITION_SEED };
+use crate::events::Borrowed;
+use crate::state::{BorrowPosition, Pool};
 
 #[derive(Accounts)]
 #[instruction(borrow_amount: u64)]  
 pub struct Borrow<'info> {
     #[account(
+        mut,
         seeds = [
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
             pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
         ],
         bump,
     )]
     pub pool: Box<Account<'info, Pool>>,
 
+    /// 出借的代币
+    pub lend_mint: Box<Account<'info, Mint>>,
+    /// 抵押品代币
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
     /// CHECK: Read only authority
     #[account(
         seeds = [
@@ -32,82 +39,31 @@ pub struct Borrow<'info> {
 
     #[account(
         mut,
-        associated_token::mint = pool.mint_a,
+        associated_token::mint = lend_mint,
         associated_token::authority = lending_pool_authority,
     )]
-    pub lending_pool_token_a: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            BORROW_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub borrow_receipt_token_mint: Box<Account<'info, Mint>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            COLLATERAL_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub collateral_receipt_token_mint: Box<Account<'info, Mint>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            BORROWER_BORROW_BLOCK_HEIGHT_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub borrower_borrow_block_height_mint: Box<Account<'info, Mint>>,
+    pub lending_pool_lend_token: Box<Account<'info, TokenAccount>>,
 
     pub borrower: Signer<'info>,
 
     #[account(
         mut,
-        associated_token::mint = pool.mint_a,
+        associated_token::mint = lend_mint,
         associated_token::authority = borrower,
     )]
-    pub borrower_token_a: Box<Account<'info, TokenAccount>>,
+    pub borrower_lend_token: Box<Account<'info, TokenAccount>>,
 
-    /// CHECK: Read only authority
     #[account(
+        mut,
         seeds = [
             pool.key().as_ref(),
+            lend_mint.key().as_ref(),
             borrower.key().as_ref(),
-            BORROWER_AUTHORITY_SEED,
+            BORROW_POSITION_SEED,
         ],
         bump,
     )]
-    pub borrower_authority: AccountInfo<'info>,
-
-    #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = borrow_receipt_token_mint,
-        associated_token::authority = borrower_authority,
-    )]
-    pub borrower_borrow_receipt_token: Box<Account<'info, TokenAccount>>,
-    
-    #[account(
-        mut,
-        associated_token::mint = collateral_receipt_token_mint,
-        associated_token::authority = borrower_authority,
-    )]
-    pub borrower_collateral_receipt_token: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        mut,
-        associated_token::mint = borrower_borrow_block_height_mint,
-        associated_token::authority = borrower_authority,
-    )]
-    pub borrower_borrow_block_height_receipt_token: Box<Account<'info, TokenAccount>>,
+    pub borrow_position: Box<Account<'info, BorrowPosition>>,
     
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -118,47 +74,46 @@ pub struct Borrow<'info> {
 }
 
 pub fn borrow(ctx: Context<Borrow>,borrow_amount: u64) -> Result<()> {
-    // 1. 计算抵押品等价于token A的数量,检查抵押率,要求抵押品等价于token A的数量大于等于借出金额的min_collateral_ratio
-    let collateral_value = ctx.accounts.pool.calculate_token_b_value(ctx.accounts.borrower_collateral_receipt_token.amount )?;
-    
-    require!(ctx.accounts.pool.check_collateral_ratio(collateral_value, borrow_amount)?,BorowError::Error10);
-
-    require!(borrow_amount <= ctx.accounts.lending_pool_token_a.amount , BorowError::Error11);
-    require!(borrow_amount <= ctx.accounts.pool.token_a_amount , BorowError::Error11);
-
-    // 4 更新pool_interest和铸造 borrow token 铸造 borrower_borrow_block_height_mint receipt_token
+    require!(borrow_amount > 0, BorowError::InvalidAmount);
+    let lend_a = ctx.accounts.pool.is_lend_a(
+        &ctx.accounts.lend_mint.key(),
+        &ctx.accounts.collateral_mint.key(),
+    )?;
+    // 1. 计息后按TWAP价格计算抵押品等价于 lend token 的数量,要求全部欠款不超过抵押品价值的max_ltv
+    let pool_key = ctx.accounts.pool.key();
+    ctx.accounts.pool.accrue_interest(pool_key, lend_a)?;
+    let borrow_index = ctx.accounts.pool.lending_market(lend_a).borrow_index;
+    let collateral_value = ctx.accounts.pool
+        .calculate_collateral_twap_value(lend_a, ctx.accounts.borrow_position.collateral)?;
+    let total_debt = ctx.accounts.borrow_position.current_debt(borrow_index)?
+        .checked_add(borrow_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    require!(ctx.accounts.pool.lending_market(lend_a).check_max_ltv(collateral_value, total_debt)?,BorowError::Error10);
+
+    let available_liquidity = ctx.accounts.pool
+        .available_liquidity(lend_a, ctx.accounts.lending_pool_lend_token.amount);
+    require!(borrow_amount <= available_liquidity , BorowError::Error11);
+
+    // 4. 按当前借款指数记录到 borrow position
+    ctx.accounts.borrow_position.rebase(total_debt, borrow_index);
+    let lending_market = ctx.accounts.pool.lending_market_mut(lend_a);
+    lending_market.total_borrows = lending_market.total_borrows
+        .checked_add(borrow_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // 5. 转移借出的代币（lend token）给借款人
     let authority_seeds = &[
         &ctx.accounts.pool.key().to_bytes(),
         LENDING_AUTHORITY_SEED,
         &[ctx.bumps.lending_pool_authority],
     ];
     let signer_seeds = &[&authority_seeds[..]];
-    ctx.accounts.pool.update_borrow_interest_accumulator(ctx.accounts.borrow_receipt_token_mint.supply)?;
-    mint_and_freeze_token(
-        &ctx.accounts.token_program,
-        &ctx.accounts.borrow_receipt_token_mint,
-        &ctx.accounts.borrower_borrow_receipt_token,
-        &ctx.accounts.lending_pool_authority,
-        signer_seeds,
-        borrow_amount,
-    )?;
-    let current_block_height: u64 = Clock::get()?.slot;
-    mint_and_freeze_token(
-        &ctx.accounts.token_program,
-        &ctx.accounts.borrower_borrow_block_height_mint,
-        &ctx.accounts.borrower_borrow_block_height_receipt_token,
-        &ctx.accounts.lending_pool_authority,
-        signer_seeds,
-        current_block_height,
-    )?;
-
-    // 5. 转移借出的代币（token A）给借款人
     token::transfer(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.lending_pool_token_a.to_account_info(),
-                to: ctx.accounts.borrower_token_a.to_account_info(),
+                from: ctx.accounts.lending_pool_lend_token.to_account_info(),
+                to: ctx.accounts.borrower_lend_token.to_account_info(),
                 authority: ctx.accounts.lending_pool_authority.to_account_info(),
             },
             signer_seeds,
@@ -166,6 +121,16 @@ pub fn borrow(ctx: Context<Borrow>,borrow_amount: u64) -> Result<()> {
         borrow_amount,
     )?;
 
+    emit!(Borrowed {
+        pool: pool_key,
+        lend_mint: ctx.accounts.lend_mint.key(),
+        borrower: ctx.accounts.borrower.key(),
+        amount: borrow_amount,
+        debt: total_debt,
+        borrow_index,
+        total_borrows: ctx.accounts.pool.lending_market(lend_a).total_borrows,
+    });
+
     Ok(())
 }
 
@@ -175,4 +140,6 @@ pub enum BorowError {
     Error10,
     #[msg("Insufficient borrow amount")]
     Error11,
+    #[msg("Invalid borrow amount")]
+    InvalidAmount,
 }
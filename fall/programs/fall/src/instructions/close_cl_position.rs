@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use crate::{
+    constants::{CL_POOL_SEED, CL_POSITION_SEED},
+    events::ClPositionClosed,
+    instructions::ClPoolError,
+    state::{ClPool, ClPosition},
+};
+
+#[derive(Accounts)]
+pub struct CloseClPosition<'info> {
+    #[account(
+        seeds = [
+            cl_pool.amm.as_ref(),
+            cl_pool.mint_a.key().as_ref(),
+            cl_pool.mint_b.key().as_ref(),
+            cl_pool.liquidity_fee.to_le_bytes().as_ref(),
+            CL_POOL_SEED,
+        ],
+        bump,
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            cl_pool.key().as_ref(),
+            owner.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref(),
+            CL_POSITION_SEED,
+        ],
+        bump,
+        has_one = owner,
+    )]
+    pub position: Box<Account<'info, ClPosition>>,
+}
+
+pub fn close_cl_position(ctx: Context<CloseClPosition>) -> Result<()> {
+    // Only empty positions can be closed: withdraw the liquidity and collect the fees first
+    let position = &ctx.accounts.position;
+    require!(
+        position.liquidity == 0 && position.tokens_owed_a == 0 && position.tokens_owed_b == 0,
+        ClPoolError::PositionNotEmpty
+    );
+
+    emit!(ClPositionClosed {
+        cl_pool: ctx.accounts.cl_pool.key(),
+        position: position.key(),
+        owner: ctx.accounts.owner.key(),
+        tick_lower: position.tick_lower,
+        tick_upper: position.tick_upper,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::{
+    constants::{AUTHORITY_SEED, CL_POOL_SEED, CL_POSITION_SEED, CL_TICK_SEED},
+    events::ClFeesCollected,
+    instructions::{modify_cl_position, ClPoolError},
+    state::{ClPool, ClPosition, ClTick},
+};
+
+#[derive(Accounts)]
+pub struct CollectClFees<'info> {
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.amm.as_ref(),
+            cl_pool.mint_a.key().as_ref(),
+            cl_pool.mint_b.key().as_ref(),
+            cl_pool.liquidity_fee.to_le_bytes().as_ref(),
+            CL_POOL_SEED,
+        ],
+        bump,
+        has_one = mint_a,
+        has_one = mint_b,
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            cl_pool.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub cl_pool_authority: AccountInfo<'info>,
+
+    pub mint_a: Box<Account<'info, Mint>>,
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_b: Box<Account<'info, TokenAccount>>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.key().as_ref(),
+            owner.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref(),
+            CL_POSITION_SEED,
+        ],
+        bump,
+        has_one = owner,
+    )]
+    pub position: Box<Account<'info, ClPosition>>,
+
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            CL_TICK_SEED,
+        ],
+        bump,
+    )]
+    pub tick_lower_account: Box<Account<'info, ClTick>>,
+
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.key().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref(),
+            CL_TICK_SEED,
+        ],
+        bump,
+    )]
+    pub tick_upper_account: Box<Account<'info, ClTick>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = owner,
+    )]
+    pub owner_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = owner,
+    )]
+    pub owner_account_b: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn collect_cl_fees(ctx: Context<CollectClFees>) -> Result<()> {
+    // Credit the fees earned since the last update without changing the liquidity
+    modify_cl_position(
+        &mut ctx.accounts.cl_pool,
+        &mut ctx.accounts.position,
+        &mut ctx.accounts.tick_lower_account,
+        &mut ctx.accounts.tick_upper_account,
+        0,
+    )?;
+
+    let amount_a = ctx.accounts.position.tokens_owed_a;
+    let amount_b = ctx.accounts.position.tokens_owed_b;
+    require!(amount_a > 0 || amount_b > 0, ClPoolError::NothingToCollect);
+
+    let authority_seeds = &[
+        &ctx.accounts.cl_pool.key().to_bytes(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.cl_pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    if amount_a > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.cl_pool_account_a.to_account_info(),
+                    to: ctx.accounts.owner_account_a.to_account_info(),
+                    authority: ctx.accounts.cl_pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.cl_pool_account_b.to_account_info(),
+                    to: ctx.accounts.owner_account_b.to_account_info(),
+                    authority: ctx.accounts.cl_pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount_b,
+        )?;
+    }
+
+    ctx.accounts.position.tokens_owed_a = 0;
+    ctx.accounts.position.tokens_owed_b = 0;
+
+    emit!(ClFeesCollected {
+        cl_pool: ctx.accounts.cl_pool.key(),
+        position: ctx.accounts.position.key(),
+        owner: ctx.accounts.owner.key(),
+        amount_a,
+        amount_b,
+    });
+
+    Ok(())
+}
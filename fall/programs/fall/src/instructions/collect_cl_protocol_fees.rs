@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{self, Mint, Token, TokenAccount, Transfer},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, CL_POOL_SEED},
+    events::ProtocolFeesCollected,
+    instructions::ClPoolError,
+    state::{Amm, ClPool},
+};
+
+#[derive(Accounts)]
+pub struct CollectClProtocolFees<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+        has_one = admin,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.amm.as_ref(),
+            cl_pool.mint_a.key().as_ref(),
+            cl_pool.mint_b.key().as_ref(),
+            cl_pool.liquidity_fee.to_le_bytes().as_ref(),
+            CL_POOL_SEED,
+        ],
+        bump,
+        has_one = amm,
+        has_one = mint_a,
+        has_one = mint_b,
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            cl_pool.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub cl_pool_authority: AccountInfo<'info>,
+
+    pub mint_a: Box<Account<'info, Mint>>,
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_b: Box<Account<'info, TokenAccount>>,
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = admin,
+    )]
+    pub admin_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = admin,
+    )]
+    pub admin_account_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn collect_cl_protocol_fees(ctx: Context<CollectClProtocolFees>) -> Result<()> {
+    let fees_a = ctx.accounts.cl_pool.protocol_fees_a;
+    let fees_b = ctx.accounts.cl_pool.protocol_fees_b;
+    require!(fees_a > 0 || fees_b > 0, ClPoolError::NothingToCollect);
+
+    let pool_key = ctx.accounts.cl_pool.key();
+    let authority_seeds = &[
+        &pool_key.to_bytes(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.cl_pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    if fees_a > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.cl_pool_account_a.to_account_info(),
+                    to: ctx.accounts.admin_account_a.to_account_info(),
+                    authority: ctx.accounts.cl_pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            fees_a,
+        )?;
+    }
+    if fees_b > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.cl_pool_account_b.to_account_info(),
+                    to: ctx.accounts.admin_account_b.to_account_info(),
+                    authority: ctx.accounts.cl_pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            fees_b,
+        )?;
+    }
+
+    ctx.accounts.cl_pool.protocol_fees_a = 0;
+    ctx.accounts.cl_pool.protocol_fees_b = 0;
+
+    emit!(ProtocolFeesCollected {
+        pool: pool_key,
+        admin: ctx.accounts.admin.key(),
+        amount_a: fees_a,
+        amount_b: fees_b,
+    });
+
+    Ok(())
+}
@@ -1,5 +1,9 @@
 use anchor_lang::prelude::*;
-use crate::state::Amm;
+use crate::{
+    constants::DEFAULT_FEE_TIERS,
+    events::AmmCreated,
+    state::Amm,
+};
 
 #[derive(Accounts)]
 #[instruction(id: Pubkey)]
@@ -29,8 +33,15 @@ pub fn create_amm(ctx: Context<CreateAmm>, id: Pubkey) -> Result<()> {
     let amm = &mut ctx.accounts.amm;
     amm.id = id;
     amm.admin = ctx.accounts.admin.key();
-    amm.liquidity_fee = 10; // 0.1%
-    amm.protocol_fee_percentage = 10; // 0.1%
+    amm.set_fees(
+        DEFAULT_FEE_TIERS.to_vec(),
+        10, // 0.1% of the liquidity fee
+    )?;
+
+    emit!(AmmCreated {
+        amm: amm.key(),
+        admin: amm.admin,
+    });
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{Mint, Token, TokenAccount},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, CL_POOL_SEED, MAX_TICK_SPACING, MIN_TICK_SPACING},
+    events::ClPoolCreated,
+    state::{Amm, ClPool},
+    tick_math::tick_at_sqrt_price,
+};
+
+#[derive(Accounts)]
+#[instruction(liquidity_fee: u16)]
+pub struct CreateClPool<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    pub mint_a: Box<Account<'info, Mint>>,
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ClPool::LEN,
+        seeds = [
+            amm.key().as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            liquidity_fee.to_le_bytes().as_ref(),
+            CL_POOL_SEED,
+        ],
+        bump,
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            cl_pool.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub cl_pool_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_cl_pool(
+    ctx: Context<CreateClPool>,
+    liquidity_fee: u16,
+    tick_spacing: u16,
+    initial_sqrt_price: u128,
+) -> Result<()> {
+    // The fee tier must be one the admin has approved
+    require!(
+        ctx.accounts.amm.fee_tiers.contains(&liquidity_fee),
+        ClPoolError::InvalidFee
+    );
+    // The tick bitmap only covers MIN_TICK..MAX_TICK from MIN_TICK_SPACING up
+    require!(
+        (MIN_TICK_SPACING..=MAX_TICK_SPACING).contains(&tick_spacing),
+        ClPoolError::InvalidTickSpacing
+    );
+
+    let pool = &mut ctx.accounts.cl_pool;
+    pool.amm = ctx.accounts.amm.key();
+    pool.mint_a = ctx.accounts.mint_a.key();
+    pool.mint_b = ctx.accounts.mint_b.key();
+    pool.liquidity_fee = liquidity_fee;
+    pool.tick_spacing = tick_spacing;
+    pool.sqrt_price = initial_sqrt_price;
+    pool.tick_current = tick_at_sqrt_price(initial_sqrt_price)?;
+
+    // The price must start inside the ticks positions can use
+    let (min_tick, max_tick) = pool.tick_range();
+    require!(
+        pool.tick_current >= min_tick && pool.tick_current < max_tick,
+        ClPoolError::InvalidSqrtPrice
+    );
+
+    emit!(ClPoolCreated {
+        cl_pool: pool.key(),
+        amm: pool.amm,
+        mint_a: pool.mint_a,
+        mint_b: pool.mint_b,
+        liquidity_fee,
+        tick_spacing,
+        sqrt_price: pool.sqrt_price,
+        tick_current: pool.tick_current,
+    });
+
+    Ok(())
+}
+
+#[error_code]
+pub enum ClPoolError {
+    #[msg("Invalid fee")]
+    InvalidFee,
+    #[msg("Invalid tick spacing")]
+    InvalidTickSpacing,
+    #[msg("Invalid sqrt price")]
+    InvalidSqrtPrice,
+    #[msg("Invalid liquidity amount")]
+    InvalidLiquidity,
+    #[msg("Invalid input amount")]
+    InvalidInput,
+    #[msg("Tick account does not match the next initialized tick")]
+    InvalidTickAccount,
+    #[msg("Output too small")]
+    OutputTooSmall,
+    #[msg("Slippage tolerance exceeded")]
+    ExcessiveSlippage,
+    #[msg("Nothing to collect")]
+    NothingToCollect,
+    #[msg("Position still has liquidity or uncollected fees")]
+    PositionNotEmpty,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
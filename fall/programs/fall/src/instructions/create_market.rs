@@ -0,0 +1,316 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{self, Mint, MintTo, Token, TokenAccount, Transfer},
+};
+use crate::{
+    constants::{
+        AUTHORITY_SEED, DEFAULT_TWAP_WINDOW, LENDING_AUTHORITY_SEED, LIQUIDITY_SEED, MAX_AMP,
+        MINIMUM_LIQUIDITY,
+    },
+    curve::{self, CurveType},
+    events::{LiquidityAdded, MarketCreated},
+    state::{Amm, InterestRateModel, LendingMarket, Observation, Pool},
+};
+
+/// Parameters both lending markets of a pool start with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LendingParams {
+    pub interest_rate_model: InterestRateModel,
+    pub max_ltv: u64,
+    pub liquidation_threshold: u64,
+    pub liquidation_bonus: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(liquidity_fee: u16)]
+pub struct CreateMarket<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    pub mint_a: Box<Account<'info, Mint>>,
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Pool::LEN,
+        seeds = [
+            amm.key().as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            liquidity_fee.to_le_bytes().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            pool.key().as_ref(),
+            LIQUIDITY_SEED,
+        ],
+        bump,
+        mint::decimals = 6,
+        mint::authority = pool_authority,
+    )]
+    pub liquidity_mint: Box<Account<'info, Mint>>,
+
+    // The token accounts are associated token accounts, anyone can create them ahead of time
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = pool_authority,
+    )]
+    pub pool_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = pool_authority,
+    )]
+    pub pool_account_b: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.key().as_ref(),
+            LENDING_AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub lending_pool_authority: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = lending_pool_authority,
+    )]
+    pub lending_pool_token_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = lending_pool_authority,
+    )]
+    pub lending_pool_token_b: Box<Account<'info, TokenAccount>>,
+
+    /// The account providing the seed liquidity
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = creator,
+    )]
+    pub creator_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = creator,
+    )]
+    pub creator_account_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = liquidity_mint,
+        associated_token::authority = creator,
+    )]
+    pub creator_account_liquidity: Box<Account<'info, TokenAccount>>,
+
+    /// The account paying for all rents
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Solana ecosystem accounts
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Create a usable market in one go: the pool seeded with `amount_a` and `amount_b`, and both of
+/// its lending markets. The seed amounts alone set the initial price, so nobody can get in first with a
+/// bad ratio. Token A must be the mint with the lower address.
+pub fn create_market(
+    ctx: Context<CreateMarket>,
+    liquidity_fee: u16,
+    curve_type: CurveType,
+    amp: u64,
+    amount_a: u64,
+    amount_b: u64,
+    lending_params: LendingParams,
+) -> Result<()> {
+    // The fee tier must be one the admin has approved
+    require!(
+        ctx.accounts.amm.fee_tiers.contains(&liquidity_fee),
+        PoolError::InvalidFee
+    );
+    // Only StableSwap pools take an amplification coefficient
+    match curve_type {
+        CurveType::ConstantProduct => require!(amp == 0, PoolError::InvalidAmp),
+        CurveType::StableSwap => require!(amp > 0 && amp <= MAX_AMP, PoolError::InvalidAmp),
+    }
+    require!(amount_a > 0 && amount_b > 0, PoolError::InvalidSeedAmount);
+
+    // One pool per pair and fee tier: the mints must be distinct and in canonical order
+    let mint_a_key = ctx.accounts.mint_a.key();
+    let mint_b_key = ctx.accounts.mint_b.key();
+    require!(mint_a_key != mint_b_key, PoolError::SameMint);
+    require!(mint_a_key < mint_b_key, PoolError::InvalidMintOrder);
+
+    // The vault addresses are known ahead of time, so anyone can send tokens to them. Only the
+    // seed amounts become reserves: tokens already in the vaults are left out of them, so they
+    // can neither move the initial price nor block the market.
+    let (reserve_a, reserve_b) = (amount_a, amount_b);
+
+    // Computing the seed liquidity on the pool's curve, some of it stays locked in the pool
+    let liquidity = curve::liquidity(curve_type, amp, reserve_a, reserve_b)?;
+    require!(MINIMUM_LIQUIDITY < liquidity, PoolError::DepositTooSmall);
+
+    let current_slot = Clock::get()?.slot;
+    let pool = &mut ctx.accounts.pool;
+    pool.amm = ctx.accounts.amm.key();
+    pool.mint_a = mint_a_key;
+    pool.mint_b = mint_b_key;
+    pool.liquidity_fee = liquidity_fee;
+    pool.curve_type = curve_type;
+    pool.amp = amp;
+    pool.token_a_amount = reserve_a;
+    pool.token_b_amount = reserve_b;
+    pool.last_price_update_slot = current_slot;
+    pool.twap_window = DEFAULT_TWAP_WINDOW;
+    pool.observations[0] = Observation {
+        slot: current_slot,
+        price_a_cumulative: 0,
+        price_b_cumulative: 0,
+    };
+    init_lending_market(&mut pool.lending_a, current_slot, &lending_params)?;
+    init_lending_market(&mut pool.lending_b, current_slot, &lending_params)?;
+
+    // Transfer the seed liquidity to the pool
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.creator_account_a.to_account_info(),
+                to: ctx.accounts.pool_account_a.to_account_info(),
+                authority: ctx.accounts.creator.to_account_info(),
+            },
+        ),
+        amount_a,
+    )?;
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.creator_account_b.to_account_info(),
+                to: ctx.accounts.pool_account_b.to_account_info(),
+                authority: ctx.accounts.creator.to_account_info(),
+            },
+        ),
+        amount_b,
+    )?;
+
+    let authority_bump = ctx.bumps.pool_authority;
+    let authority_seeds = &[
+        &ctx.accounts.amm.key().to_bytes(),
+        &ctx.accounts.mint_a.key().to_bytes(),
+        &ctx.accounts.mint_b.key().to_bytes(),
+        &liquidity_fee.to_le_bytes()[..],
+        AUTHORITY_SEED,
+        &[authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    // Mint liquidity tokens to the creator
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.liquidity_mint.to_account_info(),
+                to: ctx.accounts.creator_account_liquidity.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        liquidity - MINIMUM_LIQUIDITY,
+    )?;
+
+    emit!(MarketCreated {
+        pool: ctx.accounts.pool.key(),
+        amm: ctx.accounts.amm.key(),
+        mint_a: ctx.accounts.mint_a.key(),
+        mint_b: ctx.accounts.mint_b.key(),
+        liquidity_fee,
+        curve_type,
+        amp,
+        creator: ctx.accounts.creator.key(),
+    });
+    emit!(LiquidityAdded {
+        pool: ctx.accounts.pool.key(),
+        depositor: ctx.accounts.creator.key(),
+        amount_a,
+        amount_b,
+        liquidity: liquidity - MINIMUM_LIQUIDITY,
+        reserve_a,
+        reserve_b,
+    });
+
+    Ok(())
+}
+
+/// Start a lending market's interest indexes and set its risk parameters
+fn init_lending_market(
+    lending_market: &mut LendingMarket,
+    current_slot: u64,
+    lending_params: &LendingParams,
+) -> Result<()> {
+    lending_market.init(current_slot, lending_params.interest_rate_model)?;
+    lending_market.set_risk_params(
+        lending_params.max_ltv,
+        lending_params.liquidation_threshold,
+        lending_params.liquidation_bonus,
+    )
+}
+
+#[error_code]
+pub enum PoolError {
+    #[msg("Invalid fee")]
+    InvalidFee,
+    #[msg("Invalid amplification coefficient")]
+    InvalidAmp,
+    #[msg("Seed amounts must not be zero")]
+    InvalidSeedAmount,
+    #[msg("Deposit too small")]
+    DepositTooSmall,
+    #[msg("Token A and token B must be different mints")]
+    SameMint,
+    #[msg("Token A must be the mint with the lower address")]
+    InvalidMintOrder,
+}
@@ -1,91 +0,0 @@
-use anchor_lang::prelude::*;
-use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
-};
-use crate::{
-    constants::AUTHORITY_SEED,
-    state::{Amm, Pool},
-};
-
-#[derive(Accounts)]
-pub struct CreatePool1<'info> {
-    #[account(
-        seeds = [
-            amm.id.as_ref()
-        ],
-        bump,
-    )]
-    pub amm: Box<Account<'info, Amm>>,
-
-    pub mint_a: Box<Account<'info, Mint>>,
-    pub mint_b: Box<Account<'info, Mint>>,
-    
-    #[account(
-        init,
-        payer = payer,
-        space = Pool::LEN,
-        seeds = [
-            amm.key().as_ref(),
-            mint_a.key().as_ref(),
-            mint_b.key().as_ref(),
-        ],
-        bump,
-    )]
-    pub pool: Box<Account<'info, Pool>>,
-
-    /// CHECK: Read only authority
-    #[account(
-        seeds = [
-            amm.key().as_ref(),
-            mint_a.key().as_ref(),
-            mint_b.key().as_ref(),
-            AUTHORITY_SEED,
-        ],
-        bump,
-    )]
-    pub pool_authority: AccountInfo<'info>,
-
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = mint_a,
-        associated_token::authority = pool_authority,
-    )]
-    pub pool_account_a: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = mint_b,
-        associated_token::authority = pool_authority,
-    )]
-    pub pool_account_b: Box<Account<'info, TokenAccount>>,
-    
-    #[account(mut)]
-    pub payer: Signer<'info>,
-
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-}
-
-
-pub fn create_pool_1(ctx: Context<CreatePool1>) -> Result<()> {
-    let pool = &mut ctx.accounts.pool;
-    pool.amm = ctx.accounts.amm.key();
-    pool.mint_a = ctx.accounts.mint_a.key();
-    pool.mint_b = ctx.accounts.mint_b.key();
-    pool.borrow_interest_accumulator_block_height = Clock::get()?.slot;
-    pool.borrow_interest_accumulator = 0;
-    pool.share_lending_block_height = Clock::get()?.slot;
-    pool.share_lending_accumulator = 0;
-
-    Ok(())
-}
-
-#[error_code]
-pub enum PoolError {
-    #[msg("Invalid fee")]
-    InvalidFee,
-}
\ No newline at end of file
@@ -3,22 +3,29 @@ use anchor_spl::{
     token::{self, Mint, Token, TokenAccount, Transfer},
     associated_token::AssociatedToken,
 };
-use crate::constants::{ LENDING_AUTHORITY_SEED, BORROWER_AUTHORITY_SEED, COLLATERAL_TOKEN_SEED,BORROWER_BORROW_BLOCK_HEIGHT_TOKEN_SEED};
-use crate::state::Pool;
-use crate::instructions::utils::mint_and_freeze_token;
+use crate::constants::{ LENDING_AUTHORITY_SEED, BORROW_POSITION_SEED};
+use crate::events::CollateralDeposited;
+use crate::state::{BorrowPosition, Pool};
 
 #[derive(Accounts)]
 pub struct DepositCollateral<'info> {
     #[account(
+        mut,
         seeds = [
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
             pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
         ],
         bump,
     )]
     pub pool: Box<Account<'info, Pool>>,
-    
+
+    /// 出借的代币
+    pub lend_mint: Box<Account<'info, Mint>>,
+    /// 抵押品代币
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
     /// CHECK: Read only authority
     #[account(
         seeds = [
@@ -31,66 +38,33 @@ pub struct DepositCollateral<'info> {
 
     #[account(
         mut,
-        associated_token::mint = pool.mint_b,
+        associated_token::mint = collateral_mint,
         associated_token::authority = lending_pool_authority,
     )]
-    pub lending_pool_token_b: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            COLLATERAL_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub collateral_receipt_token_mint: Box<Account<'info, Mint>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            BORROWER_BORROW_BLOCK_HEIGHT_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub borrower_borrow_block_height_mint: Box<Account<'info, Mint>>,
+    pub lending_pool_collateral_token: Box<Account<'info, TokenAccount>>,
 
     pub borrower: Signer<'info>,
 
     #[account(
         mut,
-        associated_token::mint = pool.mint_b,
+        associated_token::mint = collateral_mint,
         associated_token::authority = borrower,
     )]
-    pub borrower_token_b: Box<Account<'info, TokenAccount>>,
+    pub borrower_collateral_token: Box<Account<'info, TokenAccount>>,
 
-    /// CHECK: Read only authority
     #[account(
+        init_if_needed,
+        payer = payer,
+        space = BorrowPosition::LEN,
         seeds = [
             pool.key().as_ref(),
+            lend_mint.key().as_ref(),
             borrower.key().as_ref(),
-            BORROWER_AUTHORITY_SEED,
+            BORROW_POSITION_SEED,
         ],
         bump,
     )]
-    pub borrower_authority: AccountInfo<'info>,
-
-    #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = collateral_receipt_token_mint,
-        associated_token::authority = borrower_authority,
-    )]
-    pub borrower_collateral_receipt_token: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = borrower_borrow_block_height_mint,
-        associated_token::authority = borrower_authority,
-    )]
-    pub borrower_borrow_block_height_receipt_token: Box<Account<'info, TokenAccount>>,
+    pub borrow_position: Box<Account<'info, BorrowPosition>>,
     
 
     #[account(mut)]
@@ -103,34 +77,49 @@ pub struct DepositCollateral<'info> {
 
 pub fn deposit_collateral(
     ctx: Context<DepositCollateral>,
-    collateral_amount: u64,  // 抵押的 token B 数量
+    collateral_amount: u64,  // 抵押的 collateral token 数量
 ) -> Result<()> {
-    // 转移抵押物（token B）到借贷池 铸造抵押物 collateral_receipt_token
+    // 计息
+    let lend_a = ctx.accounts.pool.is_lend_a(
+        &ctx.accounts.lend_mint.key(),
+        &ctx.accounts.collateral_mint.key(),
+    )?;
+    let pool_key = ctx.accounts.pool.key();
+    ctx.accounts.pool.accrue_interest(pool_key, lend_a)?;
+
+    // 转移抵押物（collateral token）到借贷池
     token::transfer(
         CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.borrower_token_b.to_account_info(),
-                to: ctx.accounts.lending_pool_token_b.to_account_info(),
+                from: ctx.accounts.borrower_collateral_token.to_account_info(),
+                to: ctx.accounts.lending_pool_collateral_token.to_account_info(),
                 authority: ctx.accounts.borrower.to_account_info(),
             },
         ),
         collateral_amount,
     )?;
-    let authority_seeds = &[
-        &ctx.accounts.pool.key().to_bytes(),
-        LENDING_AUTHORITY_SEED,
-        &[ctx.bumps.lending_pool_authority],
-    ];
-    let signer_seeds = &[&authority_seeds[..]];
-    mint_and_freeze_token(
-        &ctx.accounts.token_program,
-        &ctx.accounts.collateral_receipt_token_mint,
-        &ctx.accounts.borrower_collateral_receipt_token,
-        &ctx.accounts.lending_pool_authority,
-        signer_seeds,
-        collateral_amount,
-    )?;
-    
+
+    // 记录到 borrow position
+    let borrow_position = &mut ctx.accounts.borrow_position;
+    borrow_position.pool = ctx.accounts.pool.key();
+    borrow_position.owner = ctx.accounts.borrower.key();
+    borrow_position.lend_mint = ctx.accounts.lend_mint.key();
+    borrow_position.collateral = borrow_position.collateral
+        .checked_add(collateral_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    let lending_market = ctx.accounts.pool.lending_market_mut(lend_a);
+    lending_market.total_collateral = lending_market.total_collateral
+        .checked_add(collateral_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    emit!(CollateralDeposited {
+        pool: pool_key,
+        lend_mint: ctx.accounts.lend_mint.key(),
+        borrower: ctx.accounts.borrower.key(),
+        amount: collateral_amount,
+        collateral: ctx.accounts.borrow_position.collateral,
+    });
+
     Ok(())
 }
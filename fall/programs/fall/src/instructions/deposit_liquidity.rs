@@ -3,9 +3,11 @@ use anchor_spl::{
     associated_token::AssociatedToken,
     token::{self, Mint, MintTo, Token, TokenAccount, Transfer},
 };
-use fixed::types::I64F64;
 use crate::{
-    constants::{AUTHORITY_SEED, LIQUIDITY_SEED, MINIMUM_LIQUIDITY,PERCENT_BASE},
+    constants::{AUTHORITY_SEED, LIQUIDITY_SEED, MINIMUM_LIQUIDITY},
+    events::LiquidityAdded,
+    instructions::check_expiry,
+    math::mul_div_floor,
     state::{Pool, Amm},
 };
 
@@ -26,6 +28,7 @@ pub struct DepositLiquidity<'info> {
             amm.key().as_ref(),
             mint_a.key().as_ref(),
             mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
         ],
         bump,
     )]
@@ -37,6 +40,7 @@ pub struct DepositLiquidity<'info> {
             amm.key().as_ref(),
             mint_a.key().as_ref(),
             mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
             AUTHORITY_SEED,
         ],
         bump,
@@ -97,19 +101,6 @@ pub struct DepositLiquidity<'info> {
     )]
     pub depositor_account_b: Box<Account<'info, TokenAccount>>,
 
-    /// CHECK: Admin account from AMM state
-    #[account(
-        constraint = admin.key() == amm.admin
-    )]
-    pub admin: AccountInfo<'info>,
-
-    #[account(
-        mut,
-        associated_token::mint = liquidity_mint,
-        associated_token::authority = admin,  // Now using the admin AccountInfo
-    )]
-    pub admin_fee_account: Box<Account<'info, TokenAccount>>,
-
     /// The account paying for all rents
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -120,8 +111,21 @@ pub struct DepositLiquidity<'info> {
     pub system_program: Program<'info, System>,
 }
 
+/// Deposit up to `amount_a` and `amount_b` in the pool's current ratio. `min_amount_a` and
+/// `min_amount_b` bound the amounts actually deposited once one side is scaled down to the ratio,
+/// `min_liquidity_out` the liquidity tokens minted, i.e. the depositor's share of the pool.
+pub fn deposit_liquidity(
+    ctx: Context<DepositLiquidity>,
+    amount_a: u64,
+    amount_b: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+    min_liquidity_out: u64,
+    expiry_slot: Option<u64>,
+) -> Result<()> {
+    // Reject stale transactions
+    check_expiry(expiry_slot)?;
 
-pub fn deposit_liquidity(ctx: Context<DepositLiquidity>,amount_a: u64,amount_b: u64,) -> Result<()> {
     // Prevent depositing assets the depositor does not own
     let mut amount_a: u64 = if amount_a > ctx.accounts.depositor_account_a.amount {
         ctx.accounts.depositor_account_a.amount
@@ -134,53 +138,32 @@ pub fn deposit_liquidity(ctx: Context<DepositLiquidity>,amount_a: u64,amount_b:
         amount_b
     };
     // Making sure they are provided in the same proportion as existing liquidity
-    let pool_a = &ctx.accounts.pool_account_a;
-    let pool_b = &ctx.accounts.pool_account_b;
+    // Protocol fees and donations held in the vaults are not part of the reserves
+    let (reserve_a, reserve_b) = ctx.accounts.pool.reserves();
 
-    // Defining pool creation like this allows attackers to frontrun pool creation with bad ratios
-    let pool_creation = pool_a.amount == 0 && pool_b.amount == 0;
-    (amount_a, amount_b) = if pool_creation {
-        (amount_a, amount_b)
-    } else {
-        if pool_a.amount > pool_b.amount {
-            let new_amount_a = amount_b
-            .checked_mul(pool_a.amount)
-            .ok_or(DepositError::NumberOverflow)?
-            .checked_div(pool_b.amount)
-            .ok_or(DepositError::NumberOverflow)?;
-        (new_amount_a, amount_b)
-        } else {
-        // amount_b = amount_a * pool_b / pool_a
-        let new_amount_b = amount_a
-            .checked_mul(pool_b.amount)
-            .ok_or(DepositError::NumberOverflow)?
-            .checked_div(pool_a.amount)
-            .ok_or(DepositError::NumberOverflow)?;
-        (amount_a, new_amount_b)
-        }
-    };
+    // Pools are seeded by create_market, an empty pool has no price to deposit at
+    require!(reserve_a > 0 && reserve_b > 0, DepositError::EmptyPool);
+    (amount_a, amount_b) = optimal_amounts(amount_a, amount_b, reserve_a, reserve_b)?;
 
-    // Computing the amount of liquidity about to be deposited
-    let mut liquidity = I64F64::from_num(amount_a)
-        .checked_mul(I64F64::from_num(amount_b))
-        .unwrap()
-        .sqrt()
-        .to_num::<u64>();
+    // Slippage check on the amounts, a moved price scales one side down
+    require!(
+        amount_a >= min_amount_a && amount_b >= min_amount_b,
+        DepositError::ExcessiveSlippage
+    );
 
-    // Lock some minimum liquidity on the first deposit
-    if pool_creation {
-        require!(MINIMUM_LIQUIDITY < liquidity, DepositError::DepositTooSmall);
-        liquidity -= MINIMUM_LIQUIDITY;
-    }
+    // Liquidity tokens are minted in proportion to the share of the reserves deposited,
+    // the same share withdraw_liquidity pays out, so fees earned stay with existing holders
+    let liquidity = proportional_liquidity(
+        amount_a,
+        amount_b,
+        reserve_a,
+        reserve_b,
+        ctx.accounts.liquidity_mint.supply,
+    )?;
+    require!(liquidity > 0, DepositError::DepositTooSmall);
 
-    // Calculate protocol fee - 修改这部分逻辑
-    let user_liquidity = (liquidity as u64)
-        .checked_mul((PERCENT_BASE - ctx.accounts.amm.protocol_fee_percentage as u64) as u64)
-        .ok_or(DepositError::NumberOverflow)?
-        .checked_div(PERCENT_BASE as u64)
-        .ok_or(DepositError::NumberOverflow)? as u64;
-    
-    let protocol_fee = liquidity.checked_sub(user_liquidity).ok_or(DepositError::NumberOverflow)?;
+    // Slippage check on the minted share
+    require!(liquidity >= min_liquidity_out, DepositError::ExcessiveSlippage);
 
     // Transfer tokens to the pool
     token::transfer(
@@ -207,6 +190,7 @@ pub fn deposit_liquidity(ctx: Context<DepositLiquidity>,amount_a: u64,amount_b:
     )?;
 
     // Update pool state
+    ctx.accounts.pool.update_price_accumulators()?;
     ctx.accounts.pool.token_a_amount = ctx.accounts.pool.token_a_amount.checked_add(amount_a).ok_or(DepositError::NumberOverflow)?;
     ctx.accounts.pool.token_b_amount = ctx.accounts.pool.token_b_amount.checked_add(amount_b).ok_or(DepositError::NumberOverflow)?;
 
@@ -215,6 +199,7 @@ pub fn deposit_liquidity(ctx: Context<DepositLiquidity>,amount_a: u64,amount_b:
         &ctx.accounts.pool.amm.to_bytes(),
         &ctx.accounts.mint_a.key().to_bytes(),
         &ctx.accounts.mint_b.key().to_bytes(),
+        &ctx.accounts.pool.liquidity_fee.to_le_bytes()[..],
         AUTHORITY_SEED,
         &[authority_bump],
     ];
@@ -231,32 +216,63 @@ pub fn deposit_liquidity(ctx: Context<DepositLiquidity>,amount_a: u64,amount_b:
             },
             signer_seeds,
         ),
-        user_liquidity,
+        liquidity,
     )?;
 
-    // Mint protocol fee to admin if it's not zero
-    if protocol_fee > 0 {
-        token::mint_to(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                MintTo {
-                    mint: ctx.accounts.liquidity_mint.to_account_info(),
-                    to: ctx.accounts.admin_fee_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            protocol_fee,
-        )?;
-    }
+    emit!(LiquidityAdded {
+        pool: ctx.accounts.pool.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount_a,
+        amount_b,
+        liquidity,
+        reserve_a: ctx.accounts.pool.token_a_amount,
+        reserve_b: ctx.accounts.pool.token_b_amount,
+    });
 
     Ok(())
 }
 
+/// Largest amounts in the ratio of the reserves that stay within `amount_a` and `amount_b`:
+/// all of A with the matching B if there is enough B, otherwise all of B with the matching A
+pub fn optimal_amounts(amount_a: u64, amount_b: u64, reserve_a: u64, reserve_b: u64) -> Result<(u64, u64)> {
+    let optimal_b = mul_div_floor(amount_a as u128, reserve_b as u128, reserve_a as u128)
+        .ok_or(DepositError::NumberOverflow)?;
+    if optimal_b <= amount_b as u128 {
+        return Ok((amount_a, optimal_b as u64));
+    }
+    let optimal_a = mul_div_floor(amount_b as u128, reserve_a as u128, reserve_b as u128)
+        .ok_or(DepositError::NumberOverflow)?;
+    require!(optimal_a <= amount_a as u128, DepositError::NumberOverflow);
+    Ok((optimal_a as u64, amount_b))
+}
+
+/// Liquidity tokens for depositing `amount_a` and `amount_b` into reserves backed by `supply`
+/// liquidity tokens plus the locked MINIMUM_LIQUIDITY: the smaller of the two shares, rounded down
+pub fn proportional_liquidity(
+    amount_a: u64,
+    amount_b: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    supply: u64,
+) -> Result<u64> {
+    let total_liquidity = supply
+        .checked_add(MINIMUM_LIQUIDITY)
+        .ok_or(DepositError::NumberOverflow)? as u128;
+    let liquidity_a = mul_div_floor(amount_a as u128, total_liquidity, reserve_a as u128)
+        .ok_or(DepositError::NumberOverflow)?;
+    let liquidity_b = mul_div_floor(amount_b as u128, total_liquidity, reserve_b as u128)
+        .ok_or(DepositError::NumberOverflow)?;
+    Ok(u64::try_from(liquidity_a.min(liquidity_b)).map_err(|_| DepositError::NumberOverflow)?)
+}
+
 #[error_code]
 pub enum DepositError {
     #[msg("Deposit too small")]
     DepositTooSmall,
+    #[msg("Pool is empty")]
+    EmptyPool,
     #[msg("Number overflow")]
     NumberOverflow,
+    #[msg("Slippage tolerance exceeded")]
+    ExcessiveSlippage,
 }
@@ -0,0 +1,400 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{self, Mint, MintTo, Token, TokenAccount, Transfer},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, LIQUIDITY_SEED, PERCENT_BASE},
+    curve::{self, CurveType},
+    events::{LiquidityAdded, SwapEvent},
+    instructions::{apply_swap_fee, calculate_swap_output, check_expiry, proportional_liquidity},
+    math::U256,
+    state::{Pool, Amm},
+};
+
+/// Bisection steps before settling for the best swap amount found so far
+const MAX_SEARCH_ITERATIONS: usize = 20;
+
+/// The search also stops once the bracket is narrower than `amount / SEARCH_TOLERANCE`
+const SEARCH_TOLERANCE: u64 = 10_000;
+
+#[derive(Accounts)]
+pub struct DepositSingleSided<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            amm.key().as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    pub mint_a: Box<Account<'info, Mint>>,
+    pub mint_b: Box<Account<'info, Mint>>,
+    
+    /// The account paying for all rents
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.key().as_ref(),
+            LIQUIDITY_SEED,
+        ],
+        bump,
+        mint::decimals = 6,
+        mint::authority = pool_authority,
+    )]
+    pub liquidity_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = pool_authority,
+    )]
+    pub pool_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = pool_authority,
+    )]
+    pub pool_account_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = liquidity_mint,
+        associated_token::authority = depositor,
+    )]
+    pub depositor_account_liquidity: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = depositor,
+    )]
+    pub depositor_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = depositor,
+    )]
+    pub depositor_account_b: Box<Account<'info, TokenAccount>>,
+
+    /// The account paying for all rents
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Solana ecosystem accounts
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Deposit `amount` of token A (`deposit_a`) or token B only. The optimal part of it is swapped
+/// through the pool's own curve for the other token and the resulting pair is deposited.
+/// `min_liquidity_out` bounds the liquidity tokens actually minted.
+pub fn deposit_single_sided(
+    ctx: Context<DepositSingleSided>,
+    deposit_a: bool,
+    amount: u64,
+    min_liquidity_out: u64,
+    expiry_slot: Option<u64>,
+) -> Result<()> {
+    // Reject stale transactions
+    check_expiry(expiry_slot)?;
+
+    // Zero amount check
+    require!(amount > 0, DepositSingleSidedError::InvalidInput);
+
+    // Prevent depositing assets the depositor does not own
+    let depositor_balance = if deposit_a {
+        ctx.accounts.depositor_account_a.amount
+    } else {
+        ctx.accounts.depositor_account_b.amount
+    };
+    require!(depositor_balance >= amount, DepositSingleSidedError::InsufficientBalance);
+
+    // Protocol fees and donations held in the vaults are not part of the reserves
+    let (reserve_a, reserve_b) = ctx.accounts.pool.reserves();
+    // There is no price to swap at in an empty pool
+    require!(reserve_a > 0 && reserve_b > 0, DepositSingleSidedError::EmptyPool);
+    let (reserve_in, reserve_out) = if deposit_a {
+        (reserve_a, reserve_b)
+    } else {
+        (reserve_b, reserve_a)
+    };
+
+    // Split the input between the swap and the deposit
+    let protocol_fee_percentage = ctx.accounts.amm.protocol_fee_percentage;
+    let (swap_amount, swap_output, fee_amount) = optimal_swap(
+        &ctx.accounts.pool,
+        reserve_in,
+        reserve_out,
+        amount,
+        protocol_fee_percentage,
+    )?;
+    require!(swap_output > 0, DepositSingleSidedError::DepositTooSmall);
+    let deposit_in = amount - swap_amount;
+    let (amount_a, amount_b) = if deposit_a {
+        (deposit_in, swap_output)
+    } else {
+        (swap_output, deposit_in)
+    };
+
+    // The deposit goes into the reserves left by the swap, the swap fee stays with existing holders
+    let protocol_fee = (fee_amount as u128 * protocol_fee_percentage as u128 / PERCENT_BASE as u128) as u64;
+    let swapped_reserve_in = reserve_in
+        .checked_add(swap_amount)
+        .ok_or(DepositSingleSidedError::NumberOverflow)?;
+    let swapped_reserve_out = reserve_out
+        .checked_sub(swap_output + protocol_fee)
+        .ok_or(DepositSingleSidedError::NumberOverflow)?;
+    let (swapped_reserve_a, swapped_reserve_b) = if deposit_a {
+        (swapped_reserve_in, swapped_reserve_out)
+    } else {
+        (swapped_reserve_out, swapped_reserve_in)
+    };
+
+    // Liquidity tokens are minted in proportion to the share of the reserves deposited
+    let liquidity = proportional_liquidity(
+        amount_a,
+        amount_b,
+        swapped_reserve_a,
+        swapped_reserve_b,
+        ctx.accounts.liquidity_mint.supply,
+    )?;
+    require!(liquidity > 0, DepositSingleSidedError::DepositTooSmall);
+    // Slippage check on the minted share, after the swap has moved the price
+    require!(liquidity >= min_liquidity_out, DepositSingleSidedError::ExcessiveSlippage);
+
+    // Transfer the whole input to the pool, the swapped tokens never leave it
+    let (depositor_account_in, pool_account_in) = if deposit_a {
+        (&ctx.accounts.depositor_account_a, &ctx.accounts.pool_account_a)
+    } else {
+        (&ctx.accounts.depositor_account_b, &ctx.accounts.pool_account_b)
+    };
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: depositor_account_in.to_account_info(),
+                to: pool_account_in.to_account_info(),
+                authority: ctx.accounts.depositor.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    // Update pool state
+    ctx.accounts.pool.update_price_accumulators()?;
+    let pool = &mut ctx.accounts.pool;
+    if deposit_a {
+        pool.token_a_amount = pool.token_a_amount
+            .checked_add(amount)
+            .ok_or(DepositSingleSidedError::NumberOverflow)?;
+    } else {
+        pool.token_b_amount = pool.token_b_amount
+            .checked_add(amount)
+            .ok_or(DepositSingleSidedError::NumberOverflow)?;
+    }
+    // Take the protocol's share of the swap fee out of the reserves
+    let protocol_fee = pool.accrue_protocol_fee(!deposit_a, fee_amount, protocol_fee_percentage)?;
+
+    let authority_bump = ctx.bumps.pool_authority;
+    let authority_seeds = &[
+        &ctx.accounts.pool.amm.to_bytes(),
+        &ctx.accounts.mint_a.key().to_bytes(),
+        &ctx.accounts.mint_b.key().to_bytes(),
+        &ctx.accounts.pool.liquidity_fee.to_le_bytes()[..],
+        AUTHORITY_SEED,
+        &[authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    // Mint liquidity tokens to user
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.liquidity_mint.to_account_info(),
+                to: ctx.accounts.depositor_account_liquidity.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        liquidity,
+    )?;
+
+    let pool = &ctx.accounts.pool;
+    emit!(SwapEvent {
+        pool: pool.key(),
+        trader: ctx.accounts.depositor.key(),
+        mint_in: if deposit_a { pool.mint_a } else { pool.mint_b },
+        amount_in: swap_amount,
+        amount_out: swap_output,
+        fee_amount,
+        protocol_fee,
+        reserve_a: pool.token_a_amount,
+        reserve_b: pool.token_b_amount,
+    });
+    emit!(LiquidityAdded {
+        pool: pool.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount_a,
+        amount_b,
+        liquidity,
+        reserve_a: pool.token_a_amount,
+        reserve_b: pool.token_b_amount,
+    });
+
+    Ok(())
+}
+
+/// Largest swap amount for which the rest of `amount` is still at least in proportion to the
+/// post-swap reserves. Closed form on constant product, a bounded bisection on StableSwap.
+/// Returns `(swap_amount, swap_output, fee_amount)`
+#[inline(never)]
+fn optimal_swap(
+    pool: &Pool,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount: u64,
+    protocol_fee_percentage: u16,
+) -> Result<(u64, u64, u64)> {
+    match pool.curve_type {
+        CurveType::ConstantProduct => {
+            let swap_amount = optimal_swap_constant_product(
+                reserve_in,
+                amount,
+                pool.swap_fee()?,
+                protocol_fee_percentage,
+            )?;
+            let (output, fee_amount) = calculate_swap_output(pool, reserve_in, reserve_out, swap_amount)?;
+            Ok((swap_amount, output, fee_amount))
+        }
+        CurveType::StableSwap => optimal_swap_bisection(pool, reserve_in, reserve_out, amount, protocol_fee_percentage),
+    }
+}
+
+/// Positive root of the in-proportion condition on x * y = k. With f the swap fee taken on the output
+/// and c = f * (1 - protocol fee percentage) the part of it left in the reserves:
+/// (1 - f + c) * s^2 + ((2 - f) * reserve_in - c * amount) * s - amount * reserve_in = 0,
+/// solved with every term scaled by PERCENT_BASE^2 and rounded down
+fn optimal_swap_constant_product(
+    reserve_in: u64,
+    amount: u64,
+    swap_fee: u16,
+    protocol_fee_percentage: u16,
+) -> Result<u64> {
+    let base = U256::from(PERCENT_BASE);
+    let fee = U256::from(swap_fee);
+    let kept_fee = fee * (base - U256::from(protocol_fee_percentage));
+    let reserve_in = U256::from(reserve_in);
+    let amount_in = U256::from(amount);
+
+    let a = (base - fee) * base + kept_fee;
+    let b_pos = reserve_in * (base * 2 - fee) * base;
+    let b_neg = amount_in * kept_fee;
+    let b_abs = if b_pos >= b_neg { b_pos - b_neg } else { b_neg - b_pos };
+    let discriminant = b_abs * b_abs + U256::from(4) * a * amount_in * reserve_in * base * base;
+    // sqrt(discriminant) >= |b|, so the numerator never underflows
+    let swap_amount = (discriminant.integer_sqrt() + b_neg - b_pos) / (a * 2);
+    Ok(u64::try_from(swap_amount).map_err(|_| DepositSingleSidedError::NumberOverflow)?.min(amount))
+}
+
+/// Bisection on the pool's curve, stopped after `MAX_SEARCH_ITERATIONS` steps or once the bracket is
+/// within the tolerance. The swap fee and the curve invariant of the reserves are computed once,
+/// each step only solves the curve for the new input
+fn optimal_swap_bisection(
+    pool: &Pool,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount: u64,
+    protocol_fee_percentage: u16,
+) -> Result<(u64, u64, u64)> {
+    let swap_fee = pool.swap_fee()?;
+    let invariant = curve::swap_invariant(pool.curve_type, pool.amp, reserve_in, reserve_out)?;
+    let tolerance = amount / SEARCH_TOLERANCE;
+
+    let mut best = (0, 0, 0);
+    let (mut low, mut high) = (1u64, amount);
+    for _ in 0..MAX_SEARCH_ITERATIONS {
+        if low > high || high - low < tolerance {
+            break;
+        }
+        let swap_amount = low + (high - low) / 2;
+        let raw_output = curve::swap_output_with_invariant(
+            pool.curve_type,
+            pool.amp,
+            invariant,
+            reserve_in,
+            reserve_out,
+            swap_amount,
+        )?;
+        let (output, fee_amount) = apply_swap_fee(raw_output, swap_fee)?;
+        let protocol_fee = fee_amount as u128 * protocol_fee_percentage as u128 / PERCENT_BASE as u128;
+
+        // (amount - swap) / output >= new_reserve_in / new_reserve_out
+        let new_reserve_in = reserve_in as u128 + swap_amount as u128;
+        let new_reserve_out = (reserve_out as u128)
+            .checked_sub(output as u128 + protocol_fee)
+            .ok_or(DepositSingleSidedError::NumberOverflow)?;
+        let deposit_in = (amount - swap_amount) as u128;
+        let in_proportion = deposit_in
+            .checked_mul(new_reserve_out)
+            .zip(new_reserve_in.checked_mul(output as u128))
+            .map(|(lhs, rhs)| lhs >= rhs)
+            .ok_or(DepositSingleSidedError::NumberOverflow)?;
+
+        if in_proportion {
+            best = (swap_amount, output, fee_amount);
+            low = swap_amount + 1;
+        } else {
+            high = swap_amount - 1;
+        }
+    }
+    Ok(best)
+}
+
+#[error_code]
+pub enum DepositSingleSidedError {
+    #[msg("Invalid input amount")]
+    InvalidInput,
+    #[msg("Insufficient balance")]
+    InsufficientBalance,
+    #[msg("Pool is empty")]
+    EmptyPool,
+    #[msg("Deposit too small")]
+    DepositTooSmall,
+    #[msg("Slippage tolerance exceeded")]
+    ExcessiveSlippage,
+    #[msg("Number overflow")]
+    NumberOverflow,
+}
@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+    sysvar::instructions::{self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked},
+};
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::{
+    constants::{FLASH_LOAN_FEE, LENDING_AUTHORITY_SEED, PERCENT_BASE},
+    events::FlashBorrowed,
+    state::Pool,
+};
+
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// 出借的代币
+    pub lend_mint: Box<Account<'info, Mint>>,
+    /// 抵押品代币
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.key().as_ref(),
+            LENDING_AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub lending_pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = lend_mint,
+        associated_token::authority = lending_pool_authority,
+    )]
+    pub lending_pool_lend_token: Box<Account<'info, TokenAccount>>,
+
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = lend_mint,
+    )]
+    pub borrower_lend_token: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: 指令 sysvar, 用于检查同一交易中的还款指令
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn flash_borrow(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+    // 1. 只能由交易直接调用, 否则指令 sysvar 中看到的是外层指令
+    require!(
+        get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+        FlashLoanError::CpiNotAllowed
+    );
+
+    let lend_a = ctx.accounts.pool.is_lend_a(
+        &ctx.accounts.lend_mint.key(),
+        &ctx.accounts.collateral_mint.key(),
+    )?;
+    let pool_key = ctx.accounts.pool.key();
+    ctx.accounts.pool.accrue_interest(pool_key, lend_a)?;
+    require!(
+        ctx.accounts.pool.lending_market(lend_a).flash_loan_amount == 0,
+        FlashLoanError::FlashLoanInProgress
+    );
+
+    // 2. 最多借出借贷池中闲置的 lend token (扣除另一方向存入的抵押品)
+    let available_lend_token_amount = ctx.accounts.pool
+        .available_liquidity(lend_a, ctx.accounts.lending_pool_lend_token.amount);
+    require!(
+        amount > 0 && amount <= available_lend_token_amount,
+        FlashLoanError::InvalidAmount
+    );
+
+    // 3. 同一交易中之后必须有归还同一借贷市场的 flash_repay 指令
+    let instructions = &ctx.accounts.instructions;
+    let mut index = load_current_index_checked(instructions)? as usize + 1;
+    let repay_found = loop {
+        let Ok(instruction) = load_instruction_at_checked(index, instructions) else {
+            break false;
+        };
+        if instruction.program_id == crate::ID
+            && instruction.data.get(..8) == Some(&crate::instruction::FlashRepay::DISCRIMINATOR[..])
+            && instruction.accounts.len() >= 2
+            && instruction.accounts[0].pubkey == ctx.accounts.pool.key()
+            && instruction.accounts[1].pubkey == ctx.accounts.lend_mint.key()
+        {
+            break true;
+        }
+        index += 1;
+    };
+    require!(repay_found, FlashLoanError::MissingRepay);
+
+    // 4. 记录闪电贷并转出 lend token
+    ctx.accounts.pool.lending_market_mut(lend_a).flash_loan_amount = amount;
+
+    let authority_seeds = &[
+        &ctx.accounts.pool.key().to_bytes(),
+        LENDING_AUTHORITY_SEED,
+        &[ctx.bumps.lending_pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.lending_pool_lend_token.to_account_info(),
+                to: ctx.accounts.borrower_lend_token.to_account_info(),
+                authority: ctx.accounts.lending_pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    emit!(FlashBorrowed {
+        pool: pool_key,
+        lend_mint: ctx.accounts.lend_mint.key(),
+        borrower: ctx.accounts.borrower.key(),
+        amount,
+    });
+
+    Ok(())
+}
+
+// 闪电贷手续费, 向上取整
+pub fn flash_loan_fee(amount: u64) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(FLASH_LOAN_FEE as u128)
+        .ok_or(FlashLoanError::CalculationError)?
+        .div_ceil(PERCENT_BASE as u128);
+    Ok(u64::try_from(fee).map_err(|_| FlashLoanError::CalculationError)?)
+}
+
+#[error_code]
+pub enum FlashLoanError {
+    #[msg("Calculation error")]
+    CalculationError,
+    #[msg("Invalid flash loan amount")]
+    InvalidAmount,
+    #[msg("A flash loan is already in progress")]
+    FlashLoanInProgress,
+    #[msg("No flash loan in progress")]
+    NoFlashLoan,
+    #[msg("Flash loan is not repaid later in the transaction")]
+    MissingRepay,
+    #[msg("Flash loans cannot be taken through CPI")]
+    CpiNotAllowed,
+}
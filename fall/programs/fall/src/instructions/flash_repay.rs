@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::{
+    constants::LENDING_AUTHORITY_SEED,
+    events::FlashRepaid,
+    instructions::{flash_loan_fee, FlashLoanError},
+    state::Pool,
+};
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    // flash_borrow 按账户位置检查还款指令, pool 和 lend_mint 必须是前两个账户
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// 出借的代币
+    pub lend_mint: Box<Account<'info, Mint>>,
+    /// 抵押品代币
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.key().as_ref(),
+            LENDING_AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub lending_pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = lend_mint,
+        associated_token::authority = lending_pool_authority,
+    )]
+    pub lending_pool_lend_token: Box<Account<'info, TokenAccount>>,
+
+    pub repayer: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = lend_mint,
+        token::authority = repayer,
+    )]
+    pub repayer_lend_token: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+    let lend_a = ctx.accounts.pool.is_lend_a(
+        &ctx.accounts.lend_mint.key(),
+        &ctx.accounts.collateral_mint.key(),
+    )?;
+    let amount = ctx.accounts.pool.lending_market(lend_a).flash_loan_amount;
+    require!(amount > 0, FlashLoanError::NoFlashLoan);
+
+    // 1. 归还本金和手续费
+    let fee = flash_loan_fee(amount)?;
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.repayer_lend_token.to_account_info(),
+                to: ctx.accounts.lending_pool_lend_token.to_account_info(),
+                authority: ctx.accounts.repayer.to_account_info(),
+            },
+        ),
+        amount.checked_add(fee).ok_or(FlashLoanError::CalculationError)?,
+    )?;
+
+    // 2. 手续费计入存款指数, 结束闪电贷
+    let lending_market = ctx.accounts.pool.lending_market_mut(lend_a);
+    lending_market.accrue_flash_loan_fee(fee)?;
+    lending_market.flash_loan_amount = 0;
+    let supply_index = lending_market.supply_index;
+
+    emit!(FlashRepaid {
+        pool: ctx.accounts.pool.key(),
+        lend_mint: ctx.accounts.lend_mint.key(),
+        repayer: ctx.accounts.repayer.key(),
+        amount,
+        fee,
+        supply_index,
+    });
+
+    Ok(())
+}
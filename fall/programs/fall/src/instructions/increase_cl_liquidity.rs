@@ -0,0 +1,222 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::{
+    constants::{AUTHORITY_SEED, CL_POOL_SEED, CL_POSITION_SEED, CL_TICK_SEED},
+    events::ClLiquidityAdded,
+    instructions::{check_expiry, ClPoolError},
+    state::{ClPool, ClPosition, ClTick},
+    tick_math::{amount_a_delta, amount_b_delta, sqrt_price_at_tick},
+};
+
+#[derive(Accounts)]
+pub struct IncreaseClLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.amm.as_ref(),
+            cl_pool.mint_a.key().as_ref(),
+            cl_pool.mint_b.key().as_ref(),
+            cl_pool.liquidity_fee.to_le_bytes().as_ref(),
+            CL_POOL_SEED,
+        ],
+        bump,
+        has_one = mint_a,
+        has_one = mint_b,
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            cl_pool.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub cl_pool_authority: AccountInfo<'info>,
+
+    pub mint_a: Box<Account<'info, Mint>>,
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_b: Box<Account<'info, TokenAccount>>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.key().as_ref(),
+            owner.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref(),
+            CL_POSITION_SEED,
+        ],
+        bump,
+        has_one = owner,
+    )]
+    pub position: Box<Account<'info, ClPosition>>,
+
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            CL_TICK_SEED,
+        ],
+        bump,
+    )]
+    pub tick_lower_account: Box<Account<'info, ClTick>>,
+
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.key().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref(),
+            CL_TICK_SEED,
+        ],
+        bump,
+    )]
+    pub tick_upper_account: Box<Account<'info, ClTick>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = owner,
+    )]
+    pub owner_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = owner,
+    )]
+    pub owner_account_b: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn increase_cl_liquidity(
+    ctx: Context<IncreaseClLiquidity>,
+    liquidity: u128,
+    max_amount_a: u64,
+    max_amount_b: u64,
+    expiry_slot: Option<u64>,
+) -> Result<()> {
+    // Reject stale transactions
+    check_expiry(expiry_slot)?;
+
+    require!(
+        liquidity > 0 && liquidity <= i128::MAX as u128,
+        ClPoolError::InvalidLiquidity
+    );
+
+    let (amount_a, amount_b) = modify_cl_position(
+        &mut ctx.accounts.cl_pool,
+        &mut ctx.accounts.position,
+        &mut ctx.accounts.tick_lower_account,
+        &mut ctx.accounts.tick_upper_account,
+        liquidity as i128,
+    )?;
+
+    // Slippage check
+    require!(
+        amount_a <= max_amount_a && amount_b <= max_amount_b,
+        ClPoolError::ExcessiveSlippage
+    );
+
+    if amount_a > 0 {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_account_a.to_account_info(),
+                    to: ctx.accounts.cl_pool_account_a.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_account_b.to_account_info(),
+                    to: ctx.accounts.cl_pool_account_b.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount_b,
+        )?;
+    }
+
+    emit!(ClLiquidityAdded {
+        cl_pool: ctx.accounts.cl_pool.key(),
+        position: ctx.accounts.position.key(),
+        owner: ctx.accounts.owner.key(),
+        liquidity,
+        amount_a,
+        amount_b,
+        pool_liquidity: ctx.accounts.cl_pool.liquidity,
+    });
+
+    Ok(())
+}
+
+/// Apply a liquidity change to a position and its ticks, crediting the fees earned so far.
+/// Returns the token amounts backing the change, rounded up when adding and down when removing.
+#[inline(never)]
+pub fn modify_cl_position(
+    pool: &mut ClPool,
+    position: &mut ClPosition,
+    tick_lower: &mut ClTick,
+    tick_upper: &mut ClTick,
+    liquidity_delta: i128,
+) -> Result<(u64, u64)> {
+    if liquidity_delta != 0 {
+        if tick_lower.update(pool, liquidity_delta, false)? {
+            pool.flip_tick(tick_lower.tick_index, tick_lower.liquidity_gross > 0);
+        }
+        if tick_upper.update(pool, liquidity_delta, true)? {
+            pool.flip_tick(tick_upper.tick_index, tick_upper.liquidity_gross > 0);
+        }
+    }
+
+    let (fee_growth_inside_a, fee_growth_inside_b) = pool.fee_growth_inside(tick_lower, tick_upper);
+    position.update(liquidity_delta, fee_growth_inside_a, fee_growth_inside_b)?;
+
+    // Below the range the position is all token A, above it all token B
+    let round_up = liquidity_delta > 0;
+    let liquidity = liquidity_delta.unsigned_abs();
+    let sqrt_price_lower = sqrt_price_at_tick(position.tick_lower)?;
+    let sqrt_price_upper = sqrt_price_at_tick(position.tick_upper)?;
+    let (amount_a, amount_b) = if pool.tick_current < position.tick_lower {
+        (amount_a_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?, 0)
+    } else if pool.tick_current < position.tick_upper {
+        pool.apply_liquidity_delta(liquidity_delta)?;
+        (
+            amount_a_delta(pool.sqrt_price, sqrt_price_upper, liquidity, round_up)?,
+            amount_b_delta(sqrt_price_lower, pool.sqrt_price, liquidity, round_up)?,
+        )
+    } else {
+        (0, amount_b_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?)
+    };
+
+    Ok((
+        u64::try_from(amount_a).map_err(|_| ClPoolError::MathOverflow)?,
+        u64::try_from(amount_b).map_err(|_| ClPoolError::MathOverflow)?,
+    ))
+}
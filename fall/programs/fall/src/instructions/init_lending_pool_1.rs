@@ -1,63 +0,0 @@
-use anchor_lang::prelude::*;
-use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
-};
-use crate::{
-    constants::LENDING_AUTHORITY_SEED,
-    state::Pool,
-};
-
-#[derive(Accounts)]
-pub struct InitLendingPool1<'info> {
-    /// CHECK: Read only authority
-    #[account(
-        seeds = [
-            pool.amm.as_ref(),
-            pool.mint_a.key().as_ref(),
-            pool.mint_b.key().as_ref(),
-        ],
-        bump,
-    )]
-    pub pool: Box<Account<'info, Pool>>,
-
-    pub mint_a: Box<Account<'info, Mint>>,
-    pub mint_b: Box<Account<'info, Mint>>,
-
-    /// CHECK: Read only authority
-    #[account(
-        seeds = [
-            pool.key().as_ref(),
-            LENDING_AUTHORITY_SEED,
-        ],
-        bump,
-    )]
-    pub lending_pool_authority: AccountInfo<'info>,
-
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = mint_a,
-        associated_token::authority = lending_pool_authority,
-    )]
-    pub lending_pool_token_a: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = mint_b,
-        associated_token::authority = lending_pool_authority,
-    )]
-    pub lending_pool_token_b: Box<Account<'info, TokenAccount>>,
-
-    #[account(mut)]
-    pub payer: Signer<'info>,
-
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-}
-
-pub fn init_lending_pool_1(_ctx: Context<InitLendingPool1>) -> Result<()> {
-    Ok(())
-}
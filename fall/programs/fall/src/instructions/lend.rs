@@ -4,24 +4,31 @@ use anchor_spl::{
     token::{self, Mint, Token, TokenAccount, Transfer},
 };
 use crate::{
-    constants::{LENDING_AUTHORITY_SEED, LENDING_TOKEN_SEED,LENDER_LENDING_BLOCK_HEIGHT_TOKEN_SEED,BORROWER_AUTHORITY_SEED},
-    state::Pool,
+    constants::{LENDING_AUTHORITY_SEED, LENDING_POSITION_SEED},
+    events::Lent,
+    state::{LendingPosition, Pool},
 };
-use crate::instructions::utils::mint_and_freeze_token;
 
 
 #[derive(Accounts)]
 pub struct Lend<'info> {
     #[account(
+        mut,
         seeds = [
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
             pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
         ],
         bump,
     )]
     pub pool: Box<Account<'info, Pool>>,
 
+    /// 出借的代币
+    pub lend_mint: Box<Account<'info, Mint>>,
+    /// 抵押品代币
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
     /// CHECK: Read only authority
     #[account(
         seeds = [
@@ -34,67 +41,33 @@ pub struct Lend<'info> {
 
     #[account(
         mut,
-        associated_token::mint = pool.mint_a,
+        associated_token::mint = lend_mint,
         associated_token::authority = lending_pool_authority,
     )]
-    pub lending_pool_token_a: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            LENDING_TOKEN_SEED,
-        ],
-        bump,
-        mint::authority = lending_pool_authority,
-    )]
-    pub lending_receipt_token_mint: Box<Account<'info, Mint>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            LENDER_LENDING_BLOCK_HEIGHT_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub lender_lending_block_height_mint: Box<Account<'info, Mint>>,
+    pub lending_pool_lend_token: Box<Account<'info, TokenAccount>>,
 
     pub lender: Signer<'info>,
 
     #[account(
         mut,
-        associated_token::mint = pool.mint_a,
+        associated_token::mint = lend_mint,
         associated_token::authority = lender,
     )]
-    pub lender_token_a: Box<Account<'info, TokenAccount>>,
+    pub lender_lend_token: Box<Account<'info, TokenAccount>>,
 
-    /// CHECK: Read only authority
     #[account(
+        init_if_needed,
+        payer = payer,
+        space = LendingPosition::LEN,
         seeds = [
             pool.key().as_ref(),
+            lend_mint.key().as_ref(),
             lender.key().as_ref(),
-            BORROWER_AUTHORITY_SEED,
+            LENDING_POSITION_SEED,
         ],
         bump,
     )]
-    pub lender_authority: AccountInfo<'info>,
-
-    #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = lending_receipt_token_mint,
-        associated_token::authority = lender_authority,
-    )]
-    pub lender_lend_receipt_token: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = lender_lending_block_height_mint,
-        associated_token::authority = lender_authority,
-    )]
-    pub lender_lending_block_height_receipt_token: Box<Account<'info, TokenAccount>>,
+    pub lending_position: Box<Account<'info, LendingPosition>>,
 
     /// The account paying for all rents
     #[account(mut)]
@@ -109,53 +82,57 @@ pub struct Lend<'info> {
 pub fn lend(ctx: Context<Lend>,lender_lending_amount: u64,) -> Result<()> {
     //  todo: 限制lender_lending_amount最小额度
      require!(
-         ctx.accounts.lender_token_a.amount >= lender_lending_amount,
+         ctx.accounts.lender_lend_token.amount >= lender_lending_amount,
          LendError::InsufficientBalance
      );
+     let lend_a = ctx.accounts.pool.is_lend_a(
+         &ctx.accounts.lend_mint.key(),
+         &ctx.accounts.collateral_mint.key(),
+     )?;
  
-    //  2. 转移 token A 到借贷池
+    //  2. 转移 lend token 到借贷池
      token::transfer(
          CpiContext::new(
              ctx.accounts.token_program.to_account_info(),
              Transfer {
-                 from: ctx.accounts.lender_token_a.to_account_info(),
-                 to: ctx.accounts.lending_pool_token_a.to_account_info(),
+                 from: ctx.accounts.lender_lend_token.to_account_info(),
+                 to: ctx.accounts.lending_pool_lend_token.to_account_info(),
                  authority: ctx.accounts.lender.to_account_info(),
              },
          ),
          lender_lending_amount,
      )?;
 
-     // 3. 获取token A 收据
-     // 3.1 更新 当前lending资金时间积分 share_lending_accumulator, 编码 lending_receipt_amount
-     ctx.accounts.pool.get_updated_share_lending_accumulator(ctx.accounts.lending_receipt_token_mint.supply)?;
-
-     // 3.2 铸造 lender_lend_receipt_token
-     let authority_seeds = &[
-         &ctx.accounts.pool.key().to_bytes(),
-         LENDING_AUTHORITY_SEED,
-         &[ctx.bumps.lending_pool_authority],
-     ];
-     let signer_seeds = &[&authority_seeds[..]];
-     mint_and_freeze_token(
-        &ctx.accounts.token_program,
-        &ctx.accounts.lending_receipt_token_mint,
-        &ctx.accounts.lender_lend_receipt_token,
-        &ctx.accounts.lending_pool_authority,
-        signer_seeds,
-        lender_lending_amount,
-    )?;
-
-    //  3.3 铸造 share_lending_accumulator_receipt_token
-    let current_block_height: u64 = Clock::get()?.slot;
-    mint_and_freeze_token(
-        &ctx.accounts.token_program,
-        &ctx.accounts.lender_lending_block_height_mint,
-        &ctx.accounts.lender_lending_block_height_receipt_token,
-        &ctx.accounts.lending_pool_authority,
-        signer_seeds,
-        current_block_height,
-    )?;
+     // 3. 计息, 更新存款指数
+     let pool_key = ctx.accounts.pool.key();
+     ctx.accounts.pool.accrue_interest(pool_key, lend_a)?;
+
+    // 4. 更新 lending position: 按当前存款指数结算已有存款价值，再加上本次存款
+    let supply_index = ctx.accounts.pool.lending_market(lend_a).supply_index;
+    let lending_position = &mut ctx.accounts.lending_position;
+    lending_position.pool = ctx.accounts.pool.key();
+    lending_position.owner = ctx.accounts.lender.key();
+    lending_position.lend_mint = ctx.accounts.lend_mint.key();
+    let balance = lending_position.current_balance(supply_index)?
+        .checked_add(lender_lending_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    lending_position.rebase(balance, supply_index);
+
+    let lending_market = ctx.accounts.pool.lending_market_mut(lend_a);
+    lending_market.total_deposits = lending_market.total_deposits
+        .checked_add(lender_lending_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    let total_deposits = lending_market.total_deposits;
+
+    emit!(Lent {
+        pool: pool_key,
+        lend_mint: ctx.accounts.lend_mint.key(),
+        lender: ctx.accounts.lender.key(),
+        amount: lender_lending_amount,
+        balance,
+        supply_index,
+        total_deposits,
+    });
     Ok(())
 }
 
@@ -1,26 +1,44 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{self, Mint, Token, TokenAccount, Burn, Transfer},
+    token::{self, Mint, Token, TokenAccount, Transfer},
 };
-use crate::constants::BORROW_TOKEN_SEED;
-use crate::constants::COLLATERAL_TOKEN_SEED;
 use crate::constants::LENDING_AUTHORITY_SEED;
-use crate::constants::BORROWER_AUTHORITY_SEED;
-
-use crate::{
-    constants::AUTHORITY_SEED,
-    state:: Pool,
-};
-
-pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
-    // 1. 计算抵押品等价于token A的数量,检查抵押率,要求抵押品等价于token A的数量大于等于借出金额的min_collateral_ratio
-    let collateral_value = ctx.accounts.pool.calculate_token_b_value(
-        ctx.accounts.borrower_collateral_receipt_token.amount
+use crate::constants::BORROW_POSITION_SEED;
+use crate::constants::CLOSE_FACTOR;
+use crate::constants::PERCENT_BASE;
+use crate::events::Liquidated;
+use crate::state::{BorrowPosition, Pool};
+
+pub fn liquidate(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
+    // 1. 计息后检查仓位可被清算, 计算还款金额和扣押的抵押品
+    let lend_a = ctx.accounts.pool.is_lend_a(
+        &ctx.accounts.lend_mint.key(),
+        &ctx.accounts.collateral_mint.key(),
     )?;
-    require!(collateral_value >= ctx.accounts.borrower_borrow_receipt_token.amount, FallError::InsufficientCollateral);
+    let (debt, repay_amount, seize_amount) = prepare_liquidation(
+        &mut ctx.accounts.pool,
+        lend_a,
+        &ctx.accounts.borrow_position,
+        repay_amount,
+    )?;
+    let borrow_index = ctx.accounts.pool.lending_market(lend_a).borrow_index;
+    let collateral_amount = ctx.accounts.borrow_position.collateral;
+    let bad_debt = calculate_bad_debt(debt, repay_amount, collateral_amount, seize_amount);
+    let remaining_debt = debt - repay_amount - bad_debt;
 
-    let rewards = ctx.accounts.borrower_collateral_receipt_token.amount.checked_div(100).ok_or(FallError::CalculationError1)?;
+    // 2. 清算人偿还 lend token, 获得抵押物 collateral token
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.trader_lend_token.to_account_info(),
+                to: ctx.accounts.lending_pool_lend_token.to_account_info(),
+                authority: ctx.accounts.trader.to_account_info(),
+            },
+        ),
+        repay_amount,
+    )?;
 
     let authority_seeds = &[
         &ctx.accounts.pool.key().to_bytes(),
@@ -32,98 +50,119 @@ pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.lending_pool_token_b.to_account_info(),
-                to: ctx.accounts.trader_account_b.to_account_info(),
+                from: ctx.accounts.lending_pool_collateral_token.to_account_info(),
+                to: ctx.accounts.trader_collateral_token.to_account_info(),
                 authority: ctx.accounts.lending_pool_authority.to_account_info(),
             },
             signer_seeds,
         ),
-        rewards,
-    )?;
-    
-    let borrower_authority_seeds = &[
-        &ctx.accounts.pool.key().to_bytes(),
-        &ctx.accounts.borrower.key().to_bytes(),
-        BORROWER_AUTHORITY_SEED,
-        &[ctx.bumps.borrower_authority],
-    ];
-    let borrower_signer_seeds = &[&borrower_authority_seeds[..]];
-    token::burn(            
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Burn {
-                mint: ctx.accounts.borrow_receipt_token_mint.to_account_info(),
-                from: ctx.accounts.borrower_borrow_receipt_token.to_account_info(),
-                authority: ctx.accounts.borrower_authority.to_account_info(),
-            },
-            borrower_signer_seeds,
-        ),      
-        ctx.accounts.borrower_borrow_receipt_token.amount,          
-    )?;
-    token::burn(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Burn {
-                mint: ctx.accounts.collateral_receipt_token_mint.to_account_info(),
-                from: ctx.accounts.borrower_collateral_receipt_token.to_account_info(),
-                authority: ctx.accounts.borrower_authority.to_account_info(),
-            },
-            borrower_signer_seeds,
-        ),
-        ctx.accounts.borrower_collateral_receipt_token.amount,
+        seize_amount,
     )?;
 
-    // 更新pool_interest
-    ctx.accounts.pool.update_borrow_interest_accumulator(ctx.accounts.borrow_receipt_token_mint.supply)?;
-    
+    // 3. 更新 borrow position 和借款总额
+    let borrow_position = &mut ctx.accounts.borrow_position;
+    borrow_position.rebase(remaining_debt, borrow_index);
+    borrow_position.collateral = collateral_amount - seize_amount;
+    let lending_market = ctx.accounts.pool.lending_market_mut(lend_a);
+    lending_market.total_borrows = lending_market.total_borrows.saturating_sub(repay_amount);
+    lending_market.total_collateral = lending_market.total_collateral.saturating_sub(seize_amount);
+    lending_market.write_off_bad_debt(bad_debt)?;
+    let total_borrows = lending_market.total_borrows;
+
+    emit!(Liquidated {
+        pool: ctx.accounts.pool.key(),
+        lend_mint: ctx.accounts.lend_mint.key(),
+        borrower: ctx.accounts.borrower.key(),
+        liquidator: ctx.accounts.trader.key(),
+        repay_amount,
+        collateral_seized: seize_amount,
+        debt: remaining_debt,
+        bad_debt,
+        borrow_index,
+        total_borrows,
+    });
+
     Ok(())
 }
 
+// 计息后检查仓位可被清算, 返回 (欠款, 还款金额, 扣押的抵押品数量)
+#[inline(never)]
+pub fn prepare_liquidation(
+    pool: &mut Account<Pool>,
+    lend_a: bool,
+    borrow_position: &BorrowPosition,
+    repay_amount: u64,
+) -> Result<(u64, u64, u64)> {
+    // 按TWAP价格计算抵押品等价于 lend token 的数量,欠款超过清算阈值的仓位才可被清算
+    let pool_key = pool.key();
+    pool.accrue_interest(pool_key, lend_a)?;
+    let lending_market = *pool.lending_market(lend_a);
+    let debt = borrow_position.current_debt(lending_market.borrow_index)?;
+    let collateral_amount = borrow_position.collateral;
+    let collateral_value = pool.calculate_collateral_twap_value(lend_a, collateral_amount)?;
+    require!(
+        !lending_market.check_liquidation_threshold(collateral_value, debt)?,
+        FallError::PositionHealthy
+    );
+
+    // 单次清算最多偿还 close factor 比例的欠款 (向上取整，避免小额仓位无法清算)
+    let max_repay_amount = (debt as u128)
+        .checked_mul(CLOSE_FACTOR as u128)
+        .ok_or(FallError::CalculationError1)?
+        .div_ceil(PERCENT_BASE as u128) as u64;
+    let mut repay_amount = repay_amount.min(max_repay_amount);
+    require!(repay_amount > 0, FallError::InvalidAmount);
+
+    // 扣押的抵押品 = 还款按TWAP价格折算的 collateral token * (1 + liquidation_bonus)
+    // 抵押品不足时扣押全部抵押品，并按比例减少还款金额
+    let repay_value = pool.calculate_lend_token_twap_value(lend_a, repay_amount)?;
+    let mut seize_amount = (repay_value as u128)
+        .checked_mul(PERCENT_BASE as u128 + lending_market.liquidation_bonus as u128)
+        .ok_or(FallError::CalculationError1)?
+        / PERCENT_BASE as u128;
+    if seize_amount > collateral_amount as u128 {
+        repay_amount = (repay_amount as u128)
+            .checked_mul(collateral_amount as u128)
+            .ok_or(FallError::CalculationError1)?
+            .checked_div(seize_amount)
+            .ok_or(FallError::CalculationError1)? as u64;
+        seize_amount = collateral_amount as u128;
+    }
+    require!(repay_amount > 0, FallError::InvalidAmount);
+
+    Ok((debt, repay_amount, seize_amount as u64))
+}
+
+// 抵押品被全部扣押后剩余的欠款无法再被清算, 作为坏账核销
+pub fn calculate_bad_debt(debt: u64, repay_amount: u64, collateral_amount: u64, seize_amount: u64) -> u64 {
+    if seize_amount >= collateral_amount {
+        debt.saturating_sub(repay_amount)
+    } else {
+        0
+    }
+}
+
 
 
 #[derive(Accounts)]
 pub struct Liquidate<'info> {
     
     #[account(
+        mut,
         seeds = [
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
             pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
         ],
         bump,
-        has_one = mint_a,
-        has_one = mint_b,
     )]
     pub pool: Box<Account<'info, Pool>>,
 
-    /// CHECK: Read only authority
-    #[account(
-        seeds = [
-            pool.amm.as_ref(),
-            mint_a.key().as_ref(),
-            mint_b.key().as_ref(),
-            AUTHORITY_SEED,
-        ],
-        bump,
-    )]
-    pub pool_authority: AccountInfo<'info>,
-
-    pub mint_a: Box<Account<'info, Mint>>,
-    pub mint_b: Box<Account<'info, Mint>>,
-
-    #[account(
-        mut,
-        associated_token::mint = mint_a,
-        associated_token::authority = pool_authority,
-    )]
-    pub pool_account_a: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        mut,
-        associated_token::mint = mint_b,
-        associated_token::authority = pool_authority,
-    )]
-    pub pool_account_b: Box<Account<'info, TokenAccount>>,
+    /// 出借的代币
+    pub lend_mint: Box<Account<'info, Mint>>,
+    /// 抵押品代币
+    pub collateral_mint: Box<Account<'info, Mint>>,
 
     /// CHECK: Read only authority
     #[account(
@@ -137,69 +176,50 @@ pub struct Liquidate<'info> {
 
     #[account(
         mut,
-        associated_token::mint = mint_b,
+        associated_token::mint = lend_mint,
         associated_token::authority = lending_pool_authority,
     )]
-    pub lending_pool_token_b: Box<Account<'info, TokenAccount>>,
+    pub lending_pool_lend_token: Box<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
-        seeds = [
-            pool.key().as_ref(),
-            BORROW_TOKEN_SEED,
-        ],
-        bump,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = lending_pool_authority,
     )]
-    pub borrow_receipt_token_mint: Box<Account<'info, Mint>>,
+    pub lending_pool_collateral_token: Box<Account<'info, TokenAccount>>,
+
+    // The account doing the swap
+    pub trader: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [
-            pool.key().as_ref(),
-            COLLATERAL_TOKEN_SEED,
-        ],
-        bump,
+        associated_token::mint = lend_mint,
+        associated_token::authority = trader,
     )]
-    pub collateral_receipt_token_mint: Box<Account<'info, Mint>>,
-
-    // The account doing the swap
-    pub trader: Signer<'info>,
+    pub trader_lend_token: Box<Account<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = payer,
-        associated_token::mint = mint_b,
+        associated_token::mint = collateral_mint,
         associated_token::authority = trader,
     )]
-    pub trader_account_b: Box<Account<'info, TokenAccount>>,
+    pub trader_collateral_token: Box<Account<'info, TokenAccount>>,
 
     /// CHECK: Read only authority
     pub borrower: AccountInfo<'info>, 
 
-    /// CHECK: Read only authority
     #[account(
+        mut,
         seeds = [
             pool.key().as_ref(),
+            lend_mint.key().as_ref(),
             borrower.key().as_ref(),
-            BORROWER_AUTHORITY_SEED,
+            BORROW_POSITION_SEED,
         ],
         bump,
     )]
-    pub borrower_authority: AccountInfo<'info>,
-
-    #[account(
-        mut,
-        associated_token::mint = borrow_receipt_token_mint,
-        associated_token::authority = borrower_authority,
-    )]
-    pub borrower_borrow_receipt_token: Box<Account<'info, TokenAccount>>,
-    
-    #[account(
-        mut,
-        associated_token::mint = collateral_receipt_token_mint,
-        associated_token::authority = borrower_authority,
-    )]
-    pub borrower_collateral_receipt_token: Box<Account<'info, TokenAccount>>,
+    pub borrow_position: Box<Account<'info, BorrowPosition>>,
 
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -212,8 +232,10 @@ pub struct Liquidate<'info> {
 
 #[error_code]
 pub enum FallError {
-    #[msg("Insufficient collateral")]
-    InsufficientCollateral,
+    #[msg("Position is below the liquidation threshold")]
+    PositionHealthy,
     #[msg("Calculation error")]
     CalculationError1,
+    #[msg("Invalid liquidation amount")]
+    InvalidAmount,
 }
\ No newline at end of file
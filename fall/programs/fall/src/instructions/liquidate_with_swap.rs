@@ -0,0 +1,340 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{self, Mint, Token, TokenAccount, Transfer},
+};
+use crate::constants::LENDING_AUTHORITY_SEED;
+use crate::constants::BORROW_POSITION_SEED;
+use crate::constants::PERCENT_BASE;
+use crate::constants::LIQUIDATION_SWAP_TOLERANCE;
+
+use crate::{
+    constants::AUTHORITY_SEED,
+    curve,
+    events::{Liquidated, SwapEvent},
+    instructions::{calculate_bad_debt, calculate_swap_input, check_expiry, prepare_liquidation},
+    state::{Amm, BorrowPosition, Pool},
+};
+
+pub fn liquidate_with_swap(
+    ctx: Context<LiquidateWithSwap>,
+    repay_amount: u64,
+    max_input_amount: u64,  // 最多卖出的 collateral token 数量
+    expiry_slot: Option<u64>,
+) -> Result<()> {
+    check_expiry(expiry_slot)?;
+
+    // 1. 计息后检查仓位可被清算, 计算还款金额和扣押的抵押品
+    let lend_a = ctx.accounts.pool.is_lend_a(
+        &ctx.accounts.lend_mint.key(),
+        &ctx.accounts.collateral_mint.key(),
+    )?;
+    let (debt, repay_amount, seize_amount) = prepare_liquidation(
+        &mut ctx.accounts.pool,
+        lend_a,
+        &ctx.accounts.borrow_position,
+        repay_amount,
+    )?;
+    let borrow_index = ctx.accounts.pool.lending_market(lend_a).borrow_index;
+    let collateral_amount = ctx.accounts.borrow_position.collateral;
+
+    // 2. 通过 AMM 只卖出换回还款金额所需的抵押品, 清算奖励部分给清算人, 未卖出的部分留在借款人仓位中
+    // 扣押的抵押品 = 还款按TWAP价格折算的部分 + 清算奖励
+    let swap_budget = (seize_amount as u128)
+        .checked_mul(PERCENT_BASE as u128)
+        .ok_or(LiquidateWithSwapError::CalculationError)?
+        .checked_div(PERCENT_BASE as u128 + ctx.accounts.pool.lending_market(lend_a).liquidation_bonus as u128)
+        .ok_or(LiquidateWithSwapError::CalculationError)? as u64;
+    let bonus_amount = seize_amount - swap_budget;
+    let (reserve_a, reserve_b) = ctx.accounts.pool.reserves();
+    let (pool_lend_reserve, pool_collateral_reserve) = if lend_a {
+        (reserve_a, reserve_b)
+    } else {
+        (reserve_b, reserve_a)
+    };
+    require!(
+        pool_lend_reserve > 0 && pool_collateral_reserve > 0,
+        LiquidateWithSwapError::EmptyPool
+    );
+    let (swap_amount, fee_amount) = calculate_swap_input(
+        &ctx.accounts.pool,
+        pool_collateral_reserve,
+        pool_lend_reserve,
+        repay_amount,
+    )?;
+    require!(
+        swap_amount <= max_input_amount && swap_amount <= seize_amount,
+        LiquidateWithSwapError::ExcessiveSlippage
+    );
+
+    // 成交价不能明显低于TWAP价格, 防止清算人夹击自己的清算
+    let swap_twap_value = ctx.accounts.pool.calculate_collateral_twap_value(lend_a, swap_amount)?;
+    let min_twap_output = (swap_twap_value as u128)
+        .checked_mul((PERCENT_BASE - LIQUIDATION_SWAP_TOLERANCE) as u128)
+        .ok_or(LiquidateWithSwapError::CalculationError)?
+        / PERCENT_BASE as u128;
+    require!(repay_amount as u128 >= min_twap_output, LiquidateWithSwapError::PriceDeviation);
+
+    // 成交价差于TWAP价格时, 多卖出的抵押品从清算奖励中扣除
+    let bonus_amount = bonus_amount.min(seize_amount - swap_amount);
+    let seized_amount = swap_amount + bonus_amount;
+    let bad_debt = calculate_bad_debt(debt, repay_amount, collateral_amount, seized_amount);
+    let remaining_debt = debt - repay_amount - bad_debt;
+
+    // 3. 借贷池卖出 collateral token, AMM 池子向借贷池支付 lend token
+    ctx.accounts.pool.update_price_accumulators()?;
+
+    let lending_authority_seeds = &[
+        &ctx.accounts.pool.key().to_bytes(),
+        LENDING_AUTHORITY_SEED,
+        &[ctx.bumps.lending_pool_authority],
+    ];
+    let lending_signer_seeds = &[&lending_authority_seeds[..]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.lending_pool_collateral_token.to_account_info(),
+                to: ctx.accounts.pool_collateral_token.to_account_info(),
+                authority: ctx.accounts.lending_pool_authority.to_account_info(),
+            },
+            lending_signer_seeds,
+        ),
+        swap_amount,
+    )?;
+    if bonus_amount > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.lending_pool_collateral_token.to_account_info(),
+                    to: ctx.accounts.trader_collateral_token.to_account_info(),
+                    authority: ctx.accounts.lending_pool_authority.to_account_info(),
+                },
+                lending_signer_seeds,
+            ),
+            bonus_amount,
+        )?;
+    }
+
+    let pool_authority_seeds = &[
+        &ctx.accounts.pool.amm.to_bytes(),
+        &ctx.accounts.pool.mint_a.to_bytes(),
+        &ctx.accounts.pool.mint_b.to_bytes(),
+        &ctx.accounts.pool.liquidity_fee.to_le_bytes()[..],
+        AUTHORITY_SEED,
+        &[ctx.bumps.pool_authority],
+    ];
+    let pool_signer_seeds = &[&pool_authority_seeds[..]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.pool_lend_token.to_account_info(),
+                to: ctx.accounts.lending_pool_lend_token.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            pool_signer_seeds,
+        ),
+        repay_amount,
+    )?;
+
+    let pool_key = ctx.accounts.pool.key();
+    let pool: &mut Pool = &mut ctx.accounts.pool;
+    let (pool_lend_token_amount, pool_collateral_token_amount) = if lend_a {
+        (&mut pool.token_a_amount, &mut pool.token_b_amount)
+    } else {
+        (&mut pool.token_b_amount, &mut pool.token_a_amount)
+    };
+    *pool_lend_token_amount = pool_lend_token_amount
+        .checked_sub(repay_amount)
+        .ok_or(LiquidateWithSwapError::CalculationError)?;
+    *pool_collateral_token_amount = pool_collateral_token_amount
+        .checked_add(swap_amount)
+        .ok_or(LiquidateWithSwapError::CalculationError)?;
+    let protocol_fee = pool.accrue_protocol_fee(lend_a, fee_amount, ctx.accounts.amm.protocol_fee_percentage)?;
+
+    // 卖出后池子的不变量不能减少 (手续费留在池子中)
+    let new_collateral_reserve = pool_collateral_reserve
+        .checked_add(swap_amount)
+        .ok_or(LiquidateWithSwapError::CalculationError)?;
+    let new_lend_reserve = pool_lend_reserve
+        .checked_sub(repay_amount + protocol_fee)
+        .ok_or(LiquidateWithSwapError::CalculationError)?;
+    require!(
+        curve::invariant_holds(
+            pool.curve_type,
+            pool.amp,
+            pool_collateral_reserve,
+            pool_lend_reserve,
+            new_collateral_reserve,
+            new_lend_reserve,
+        )?,
+        LiquidateWithSwapError::InvariantViolated
+    );
+
+    emit!(SwapEvent {
+        pool: pool_key,
+        trader: ctx.accounts.lending_pool_authority.key(),
+        mint_in: ctx.accounts.collateral_mint.key(),
+        amount_in: swap_amount,
+        amount_out: repay_amount,
+        fee_amount,
+        protocol_fee,
+        reserve_a: pool.token_a_amount,
+        reserve_b: pool.token_b_amount,
+    });
+
+    // 4. 更新 borrow position 和借款总额
+    let borrow_position = &mut ctx.accounts.borrow_position;
+    borrow_position.rebase(remaining_debt, borrow_index);
+    borrow_position.collateral = collateral_amount - seized_amount;
+    let lending_market = ctx.accounts.pool.lending_market_mut(lend_a);
+    lending_market.total_borrows = lending_market.total_borrows.saturating_sub(repay_amount);
+    lending_market.total_collateral = lending_market.total_collateral.saturating_sub(seized_amount);
+    lending_market.write_off_bad_debt(bad_debt)?;
+    let total_borrows = lending_market.total_borrows;
+
+    emit!(Liquidated {
+        pool: pool_key,
+        lend_mint: ctx.accounts.lend_mint.key(),
+        borrower: ctx.accounts.borrower.key(),
+        liquidator: ctx.accounts.trader.key(),
+        repay_amount,
+        collateral_seized: seized_amount,
+        debt: remaining_debt,
+        bad_debt,
+        borrow_index,
+        total_borrows,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct LiquidateWithSwap<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// 出借的代币
+    pub lend_mint: Box<Account<'info, Mint>>,
+    /// 抵押品代币
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = lend_mint,
+        associated_token::authority = pool_authority,
+    )]
+    pub pool_lend_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = pool_authority,
+    )]
+    pub pool_collateral_token: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.key().as_ref(),
+            LENDING_AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub lending_pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = lend_mint,
+        associated_token::authority = lending_pool_authority,
+    )]
+    pub lending_pool_lend_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = lending_pool_authority,
+    )]
+    pub lending_pool_collateral_token: Box<Account<'info, TokenAccount>>,
+
+    pub trader: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = trader,
+    )]
+    pub trader_collateral_token: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    pub borrower: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.key().as_ref(),
+            lend_mint.key().as_ref(),
+            borrower.key().as_ref(),
+            BORROW_POSITION_SEED,
+        ],
+        bump,
+    )]
+    pub borrow_position: Box<Account<'info, BorrowPosition>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[error_code]
+pub enum LiquidateWithSwapError {
+    #[msg("Calculation error")]
+    CalculationError,
+    #[msg("Invalid liquidation amount")]
+    InvalidAmount,
+    #[msg("Pool is empty")]
+    EmptyPool,
+    #[msg("Slippage tolerance exceeded")]
+    ExcessiveSlippage,
+    #[msg("Swap price deviates too far from the TWAP")]
+    PriceDeviation,
+    #[msg("Invariant violated")]
+    InvariantViolated,
+}
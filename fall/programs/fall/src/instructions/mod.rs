@@ -1,35 +1,71 @@
 mod create_amm;
-mod create_pool_1;
-mod create_pool_2;
+mod create_market;
 mod deposit_liquidity;
+mod deposit_single_sided;
 mod withdraw_liquidity;
 mod swap_exact_tokens_for_tokens;
+mod swap_tokens_for_exact_tokens;
+mod swap_route;
 pub mod price;
 mod borrow;
 mod redeem;
 mod repay;
 mod lend;
 mod liquidate;
-mod init_lending_pool_1;
-mod init_lending_pool_2;
-mod init_lending_pool_3;
+mod liquidate_with_swap;
+mod flash_borrow;
+mod flash_repay;
 mod utils;
 mod deposit_collateral;
+mod withdraw_collateral;
+mod set_twap_window;
+mod set_dynamic_fee;
+mod set_risk_params;
+mod collect_protocol_fees;
+mod set_fees;
+mod propose_admin;
+mod accept_admin;
+mod create_cl_pool;
+mod open_cl_position;
+mod increase_cl_liquidity;
+mod decrease_cl_liquidity;
+mod collect_cl_fees;
+mod close_cl_position;
+mod swap_cl;
+mod collect_cl_protocol_fees;
 
 pub use create_amm::*;  
-pub use create_pool_1::*;
-pub use create_pool_2::*;
+pub use create_market::*;
 pub use deposit_liquidity::*;
+pub use deposit_single_sided::*;
 pub use withdraw_liquidity::*;
 pub use swap_exact_tokens_for_tokens::*;
+pub use swap_tokens_for_exact_tokens::*;
+pub use swap_route::*;
 pub use price::*;
 pub use borrow::*;
 pub use redeem::*;
 pub use repay::*;
 pub use lend::*;
 pub use liquidate::*;
-pub use init_lending_pool_1::*;
-pub use init_lending_pool_2::*;
-pub use init_lending_pool_3::*;
+pub use liquidate_with_swap::*;
+pub use flash_borrow::*;
+pub use flash_repay::*;
 pub use deposit_collateral::*;
+pub use withdraw_collateral::*;
+pub use set_twap_window::*;
+pub use set_dynamic_fee::*;
+pub use set_risk_params::*;
+pub use collect_protocol_fees::*;
+pub use set_fees::*;
+pub use propose_admin::*;
+pub use accept_admin::*;
+pub use create_cl_pool::*;
+pub use open_cl_position::*;
+pub use increase_cl_liquidity::*;
+pub use decrease_cl_liquidity::*;
+pub use collect_cl_fees::*;
+pub use close_cl_position::*;
+pub use swap_cl::*;
+pub use collect_cl_protocol_fees::*;
 pub use utils::*;
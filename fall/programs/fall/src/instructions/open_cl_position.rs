@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+use crate::{
+    constants::{CL_POOL_SEED, CL_POSITION_SEED, CL_TICK_SEED},
+    events::ClPositionOpened,
+    state::{ClPool, ClPosition, ClTick},
+};
+
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenClPosition<'info> {
+    #[account(
+        seeds = [
+            cl_pool.amm.as_ref(),
+            cl_pool.mint_a.key().as_ref(),
+            cl_pool.mint_b.key().as_ref(),
+            cl_pool.liquidity_fee.to_le_bytes().as_ref(),
+            CL_POOL_SEED,
+        ],
+        bump,
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ClPosition::LEN,
+        seeds = [
+            cl_pool.key().as_ref(),
+            owner.key().as_ref(),
+            tick_lower.to_le_bytes().as_ref(),
+            tick_upper.to_le_bytes().as_ref(),
+            CL_POSITION_SEED,
+        ],
+        bump,
+    )]
+    pub position: Box<Account<'info, ClPosition>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ClTick::LEN,
+        seeds = [
+            cl_pool.key().as_ref(),
+            tick_lower.to_le_bytes().as_ref(),
+            CL_TICK_SEED,
+        ],
+        bump,
+    )]
+    pub tick_lower_account: Box<Account<'info, ClTick>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ClTick::LEN,
+        seeds = [
+            cl_pool.key().as_ref(),
+            tick_upper.to_le_bytes().as_ref(),
+            CL_TICK_SEED,
+        ],
+        bump,
+    )]
+    pub tick_upper_account: Box<Account<'info, ClTick>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn open_cl_position(ctx: Context<OpenClPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+    ctx.accounts.cl_pool.check_tick_range(tick_lower, tick_upper)?;
+
+    let position = &mut ctx.accounts.position;
+    position.cl_pool = ctx.accounts.cl_pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.tick_lower = tick_lower;
+    position.tick_upper = tick_upper;
+
+    // Tick accounts are shared between positions, only set them up once
+    for (tick, tick_index) in [
+        (&mut ctx.accounts.tick_lower_account, tick_lower),
+        (&mut ctx.accounts.tick_upper_account, tick_upper),
+    ] {
+        if tick.cl_pool == Pubkey::default() {
+            tick.cl_pool = ctx.accounts.cl_pool.key();
+            tick.tick_index = tick_index;
+        }
+    }
+
+    emit!(ClPositionOpened {
+        cl_pool: ctx.accounts.cl_pool.key(),
+        position: ctx.accounts.position.key(),
+        owner: ctx.accounts.owner.key(),
+        tick_lower,
+        tick_upper,
+    });
+
+    Ok(())
+}
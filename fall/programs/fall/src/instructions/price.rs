@@ -5,6 +5,7 @@ use anchor_spl::{
 };
 use crate::{
     constants::AUTHORITY_SEED,
+    curve,
     state::Pool,
 };
 
@@ -16,6 +17,7 @@ pub struct Price<'info> {
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
             pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
         ],
         bump,
         has_one = mint_a,
@@ -29,6 +31,7 @@ pub struct Price<'info> {
             pool.amm.as_ref(),
             mint_a.key().as_ref(),
             mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
             AUTHORITY_SEED,
         ],
         bump,
@@ -62,13 +65,29 @@ pub struct Price<'info> {
 pub struct PriceResult {
     pub token_a_amount: u64,
     pub token_b_amount: u64,
+    /// Spot price of token A in token B on the pool's curve (Q64.64), 0 for an empty pool
+    pub price_a: u128,
+    /// Spot price of token B in token A on the pool's curve (Q64.64), 0 for an empty pool
+    pub price_b: u128,
 }
 
 pub fn price(
     ctx: Context<Price>,
 ) -> Result<PriceResult> {
+    let (token_a_amount, token_b_amount) = ctx.accounts.pool.reserves();
+    let (price_a, price_b) = if token_a_amount > 0 && token_b_amount > 0 {
+        let pool = &ctx.accounts.pool;
+        (
+            curve::spot_price(pool.curve_type, pool.amp, token_a_amount, token_b_amount)?,
+            curve::spot_price(pool.curve_type, pool.amp, token_b_amount, token_a_amount)?,
+        )
+    } else {
+        (0, 0)
+    };
     Ok(PriceResult {
-        token_a_amount: ctx.accounts.pool_account_a.amount,
-        token_b_amount: ctx.accounts.pool_account_b.amount,
+        token_a_amount,
+        token_b_amount,
+        price_a,
+        price_b,
     })
 }
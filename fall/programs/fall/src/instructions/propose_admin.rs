@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+use crate::{events::AdminProposed, state::Amm};
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+        has_one = admin,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    pub admin: Signer<'info>,
+}
+
+/// Propose a new admin, who has to accept before taking over.
+/// Proposing the default pubkey cancels a pending proposal.
+pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    let amm = &mut ctx.accounts.amm;
+    amm.pending_admin = new_admin;
+
+    emit!(AdminProposed {
+        amm: amm.key(),
+        admin: amm.admin,
+        pending_admin: new_admin,
+    });
+
+    Ok(())
+}
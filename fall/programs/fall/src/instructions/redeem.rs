@@ -1,9 +1,10 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token::{self, Mint, Token, TokenAccount, Transfer, Burn},
+    token::{self, Mint, Token, TokenAccount, Transfer},
     associated_token::AssociatedToken,
 };
 use crate::constants::*;
+use crate::events::Redeemed;
 use crate::state::*;
 
 
@@ -11,29 +12,21 @@ use crate::state::*;
 #[derive(Accounts)]
 pub struct Redeem<'info> {
     #[account(
+        mut,
         seeds = [
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
             pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
         ],
         bump,
     )]
     pub pool: Box<Account<'info, Pool>>,
 
-    /// CHECK: Read only authority
-    #[account(
-        seeds = [
-            pool.amm.as_ref(),
-            pool.mint_a.key().as_ref(),
-            pool.mint_b.key().as_ref(),
-            AUTHORITY_SEED,
-        ],
-        bump,
-    )]
-    pub pool_authority: AccountInfo<'info>,
-
-    pub mint_a: Box<Account<'info, Mint>>,
-    pub mint_b: Box<Account<'info, Mint>>,
+    /// 出借的代币
+    pub lend_mint: Box<Account<'info, Mint>>,
+    /// 抵押品代币
+    pub collateral_mint: Box<Account<'info, Mint>>,
 
     /// CHECK: Read only authority
     #[account(
@@ -47,99 +40,46 @@ pub struct Redeem<'info> {
 
     #[account(
         mut,
-        associated_token::mint = pool.mint_a,
+        associated_token::mint = lend_mint,
         associated_token::authority = lending_pool_authority,
     )]
-    pub lending_pool_token_a: Box<Account<'info, TokenAccount>>,
+    pub lending_pool_lend_token: Box<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
-        associated_token::mint = mint_b,
+        associated_token::mint = collateral_mint,
         associated_token::authority = lending_pool_authority,
     )]
-    pub lending_pool_token_b: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            LENDING_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub lending_receipt_token_mint: Box<Account<'info, Mint>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            BORROW_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub borrow_receipt_token_mint: Box<Account<'info, Mint>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            COLLATERAL_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub collateral_receipt_token_mint: Box<Account<'info, Mint>>,
-
-    #[account(
-        mut,
-        seeds = [
-            pool.key().as_ref(),
-            LENDER_LENDING_BLOCK_HEIGHT_TOKEN_SEED,
-        ],
-        bump,
-    )]
-    pub lender_lending_block_height_mint: Box<Account<'info, Mint>>,
+    pub lending_pool_collateral_token: Box<Account<'info, TokenAccount>>,
 
     pub lender: Signer<'info>,
 
     #[account(
         mut,
-        associated_token::mint = mint_a,
+        associated_token::mint = lend_mint,
         associated_token::authority = lender,
     )]
-    pub lender_token_a: Box<Account<'info, TokenAccount>>,
+    pub lender_lend_token: Box<Account<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = payer,
-        associated_token::mint = mint_b,
+        associated_token::mint = collateral_mint,
         associated_token::authority = lender,
     )]
-    pub lender_token_b: Box<Account<'info, TokenAccount>>,
+    pub lender_collateral_token: Box<Account<'info, TokenAccount>>,
 
-    /// CHECK: Read only authority
     #[account(
+        mut,
         seeds = [
             pool.key().as_ref(),
+            lend_mint.key().as_ref(),
             lender.key().as_ref(),
-            BORROWER_AUTHORITY_SEED,
+            LENDING_POSITION_SEED,
         ],
         bump,
     )]
-    pub lender_authority: AccountInfo<'info>,
-
-    #[account(
-        mut,
-        associated_token::mint = lending_receipt_token_mint,
-        associated_token::authority = lender_authority,
-    )]
-    pub lender_lending_receipt_token: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        mut,
-        associated_token::mint = lender_lending_block_height_mint,
-        associated_token::authority = lender_authority,
-    )]
-    pub lender_lending_block_height_receipt_token: Box<Account<'info, TokenAccount>>,
+    pub lending_position: Box<Account<'info, LendingPosition>>,
 
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -150,84 +90,44 @@ pub struct Redeem<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
-    // 1. 解码 lending receipt token 数量
-    let lender_lending_receipt_amount = ctx.accounts.lender_lending_receipt_token.amount;
-    // todo: 限制redeem时间间隔，如果redeem时间间隔小于*天，则不能redeem（可以通过限制share_lending_accumulator来实现）
-
-    // 2. 计算lender实际累积资金时间成本: 区块数 * lending资金数
-    let last_lending_block_height = ctx.accounts.lender_lending_block_height_receipt_token.amount;
-    let current_lending_block_height = Clock::get()?.slot;
-    let blocks_passed = current_lending_block_height.checked_sub(last_lending_block_height).ok_or(ProgramError::ArithmeticOverflow)?;
-    let lender_asset_accumulator = blocks_passed.checked_mul(lender_lending_receipt_amount).ok_or(ProgramError::ArithmeticOverflow)?;
-
-    // 计算lendingpool实际累积资金时间成本
-    let last_share_lending_accumulator = ctx.accounts.pool.share_lending_accumulator;
-    let current_share_lending_accumulator =ctx.accounts.pool.get_updated_share_lending_accumulator(ctx.accounts.lending_receipt_token_mint.supply)?;
-    let lendingpool_asset_accumulator = current_share_lending_accumulator.checked_sub(last_share_lending_accumulator).ok_or(RedeemError::CalculationError)?;
-
-    // 计算利息: lender_asset_accumulator/lendingpool_asset_accumulator*borrow_interest_accumulator
-    let borrow_interest_accumulator = ctx.accounts.pool.borrow_interest_accumulator;
-    let mut lending_pool_token_b_amount = ctx.accounts.lending_pool_token_b.amount;
-
-    if lendingpool_asset_accumulator!=0{
-        let earn_interest = lender_asset_accumulator.checked_mul(borrow_interest_accumulator).ok_or(RedeemError::CalculationError)?
-        .checked_div(lendingpool_asset_accumulator).ok_or(RedeemError::CalculationError)?;
-        // 3. 转移利息
-        if earn_interest > 0 {
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.lending_pool_token_b.to_account_info(),
-                        to: ctx.accounts.lender_token_b.to_account_info(),
-                        authority: ctx.accounts.lending_pool_authority.to_account_info(),
-                    },
-                ),
-                earn_interest,
-            )?;
-            lending_pool_token_b_amount = lending_pool_token_b_amount.checked_sub(earn_interest).ok_or(RedeemError::CalculationError)?; 
-        }
-    }
-
-    // // 4. 解冻、销毁 lending receipt token
-    let lender_authority_seeds = &[
-        &ctx.accounts.pool.key().to_bytes(),
-        &ctx.accounts.lender.key().to_bytes(),
-        BORROWER_AUTHORITY_SEED,
-        &[ctx.bumps.lender_authority],
-    ];
-    let lender_authority_signer_seeds = &[&lender_authority_seeds[..]];
-    token::burn(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Burn {
-                mint: ctx.accounts.lending_receipt_token_mint.to_account_info(),
-                from: ctx.accounts.lender_lending_receipt_token.to_account_info(),
-                authority: ctx.accounts.lender_authority.to_account_info(),
-            }, 
-            lender_authority_signer_seeds,
-        ),
-        lender_lending_receipt_amount,
-    )?;
-
-    // 更新lendingpool资金成本，因为lender已经将他的资金和利息提走了
-    ctx.accounts.pool.reduce_share_lending_accumulator(lender_asset_accumulator)?;
-    token::burn(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Burn {
-                mint: ctx.accounts.lender_lending_block_height_mint.to_account_info(),
-                from: ctx.accounts.lender_lending_block_height_receipt_token.to_account_info(),
-                authority: ctx.accounts.lender_authority.to_account_info(),
-            }, 
-            lender_authority_signer_seeds,
-        ),
-        last_lending_block_height,
+pub fn redeem(ctx: Context<Redeem>, amount: u64) -> Result<()> {
+    // 1. 计息, 按当前存款指数计算存款价值(本金+利息)
+    // todo: 限制redeem时间间隔，如果redeem时间间隔小于*天，则不能redeem
+    let lend_a = ctx.accounts.pool.is_lend_a(
+        &ctx.accounts.lend_mint.key(),
+        &ctx.accounts.collateral_mint.key(),
     )?;
-
-    // 5. 提取本金
-    // 计算lender借出的token b 等价于抵押品token a的数量 todo: 取整方向
+    let pool_key = ctx.accounts.pool.key();
+    ctx.accounts.pool.accrue_interest(pool_key, lend_a)?;
+    let supply_index = ctx.accounts.pool.lending_market(lend_a).supply_index;
+    let balance = ctx.accounts.lending_position.current_balance(supply_index)?;
+
+    // 赎回 amount 数量的存款价值 (lend token), 不超过当前存款价值
+    require!(amount > 0 && amount <= balance, RedeemError::InvalidAmount);
+
+    // 借贷池中可赎回的 lend token (扣除另一方向存入的抵押品)
+    let available_lend_token_amount = ctx.accounts.pool
+        .available_liquidity(lend_a, ctx.accounts.lending_pool_lend_token.amount);
+    // 已清算造成的 lend token 缺口: 存款总额 - 借款总额 - 未归还的闪电贷 - 借贷池中可赎回的 lend token
+    let lending_market = ctx.accounts.pool.lending_market(lend_a);
+    let liquidated_lend_token_amount = lending_market.total_deposits
+        .saturating_sub(lending_market.total_borrows)
+        .saturating_sub(lending_market.flash_loan_amount)
+        .saturating_sub(available_lend_token_amount);
+    // 借贷池中不属于任何抵押品或另一方向存款的 collateral token
+    let other_market = ctx.accounts.pool.lending_market(!lend_a);
+    let avaliable_collateral_token_amount = ctx.accounts.lending_pool_collateral_token.amount
+        .saturating_sub(lending_market.total_collateral)
+        .saturating_sub(other_market.total_deposits.saturating_sub(other_market.total_borrows));
+
+    // 2. 更新 lending position
+    ctx.accounts.lending_position.rebase(balance - amount, supply_index);
+    let lending_market = ctx.accounts.pool.lending_market_mut(lend_a);
+    lending_market.total_deposits = lending_market.total_deposits
+        .checked_sub(amount)
+        .ok_or(RedeemError::CalculationError)?;
+
+    // 3. 提取本金和利息
     let authority_seeds = &[
         &ctx.accounts.pool.key().to_bytes(),
         LENDING_AUTHORITY_SEED,
@@ -235,60 +135,68 @@ pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
     ];
     let signer_seeds = &[&authority_seeds[..]];
 
-    // 如果借贷池中的未借出的token a 数量大于等于用户借出的token a 数量，则直接转移
-    if ctx.accounts.lending_pool_token_a.amount >= lender_lending_receipt_amount{
+    // 如果借贷池中的未借出的 lend token 数量大于等于用户的存款价值，则直接转移
+    let (lend_token_amount, collateral_token_amount) = if available_lend_token_amount >= amount{
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.lending_pool_token_a.to_account_info(),
-                    to: ctx.accounts.lender_token_a.to_account_info(),
+                    from: ctx.accounts.lending_pool_lend_token.to_account_info(),
+                    to: ctx.accounts.lender_lend_token.to_account_info(),
                     authority: ctx.accounts.lending_pool_authority.to_account_info(),
                 },
                 signer_seeds,
             ),
-            lender_lending_receipt_amount,
+            amount,
         )?;
+        (amount, 0)
     }else{
-        // 如果已经清算的token a 数量大于用户借出的token a 数量，那么lender此时redeem只能得到borrower被清算的抵押物token b
-        // 或者lender可以等待其他borrower repay后，pool中有足够的token a时再redeem
-        let liquidated_token_a_amount = ctx.accounts.lending_receipt_token_mint.supply
-        .checked_sub(ctx.accounts.borrow_receipt_token_mint.supply).ok_or(RedeemError::CalculationError)?
-        .checked_sub(ctx.accounts.lending_pool_token_a.amount).ok_or(RedeemError::CalculationError)?;
-
+        // 如果已经清算的 lend token 数量大于用户借出的数量，那么lender此时redeem只能得到borrower被清算的抵押物
+        // 或者lender可以等待其他borrower repay后，pool中有足够的 lend token 时再redeem
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.lending_pool_token_a.to_account_info(),
-                    to: ctx.accounts.lender_token_a.to_account_info(),
+                    from: ctx.accounts.lending_pool_lend_token.to_account_info(),
+                    to: ctx.accounts.lender_lend_token.to_account_info(),
                     authority: ctx.accounts.lending_pool_authority.to_account_info(),
                 },
                 signer_seeds,
             ),
-            ctx.accounts.lending_pool_token_a.amount,
+            available_lend_token_amount,
         )?;
-        let remaining_redeem_token_a_amount = lender_lending_receipt_amount.checked_sub(ctx.accounts.lending_pool_token_a.amount).ok_or(RedeemError::CalculationError)?;
+        let remaining_redeem_lend_token_amount = amount.checked_sub(available_lend_token_amount).ok_or(RedeemError::CalculationError)?;
 
-        require!(remaining_redeem_token_a_amount<=liquidated_token_a_amount, RedeemError::CalculationError);
-        let avaliable_token_b_amount: u64 = lending_pool_token_b_amount-ctx.accounts.collateral_receipt_token_mint.supply;
-        let redeem_token_b_amount = avaliable_token_b_amount
-        .checked_mul(remaining_redeem_token_a_amount).ok_or(RedeemError::CalculationError)?
-        .checked_div(liquidated_token_a_amount).ok_or(RedeemError::CalculationError)?;
+        require!(remaining_redeem_lend_token_amount<=liquidated_lend_token_amount, RedeemError::InsufficientLiquidity);
+        let redeem_collateral_token_amount = (avaliable_collateral_token_amount as u128)
+        .checked_mul(remaining_redeem_lend_token_amount as u128).ok_or(RedeemError::CalculationError)?
+        .checked_div(liquidated_lend_token_amount as u128).ok_or(RedeemError::CalculationError)? as u64;
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.lending_pool_token_b.to_account_info(),
-                    to: ctx.accounts.lender_token_b.to_account_info(),
+                    from: ctx.accounts.lending_pool_collateral_token.to_account_info(),
+                    to: ctx.accounts.lender_collateral_token.to_account_info(),
                     authority: ctx.accounts.lending_pool_authority.to_account_info(),
                 },
                 signer_seeds,
             ),
-            redeem_token_b_amount,
+            redeem_collateral_token_amount,
         )?;
-    }  
-   
+        (available_lend_token_amount, redeem_collateral_token_amount)
+    };
+
+    emit!(Redeemed {
+        pool: pool_key,
+        lend_mint: ctx.accounts.lend_mint.key(),
+        lender: ctx.accounts.lender.key(),
+        redeemed: amount,
+        amount: lend_token_amount,
+        collateral_amount: collateral_token_amount,
+        supply_index,
+        total_deposits: ctx.accounts.pool.lending_market(lend_a).total_deposits,
+    });
+
     Ok(())
 }
 
@@ -296,4 +204,8 @@ pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
 pub enum RedeemError {
     #[msg("Calculation error")]
     CalculationError,
+    #[msg("Invalid redeem amount")]
+    InvalidAmount,
+    #[msg("Not enough lend token or liquidated collateral to redeem")]
+    InsufficientLiquidity,
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use crate::{
+    constants::MAX_LIQUIDITY_FEE,
+    events::DynamicFeeUpdated,
+    state::{Amm, Pool},
+};
+
+#[derive(Accounts)]
+pub struct SetDynamicFee<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+        has_one = admin,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub admin: Signer<'info>,
+}
+
+pub fn set_dynamic_fee(
+    ctx: Context<SetDynamicFee>,
+    enabled: bool,
+    min_fee: u16,
+    max_fee: u16,
+) -> Result<()> {
+    require!(
+        min_fee <= max_fee && max_fee as u64 <= MAX_LIQUIDITY_FEE,
+        DynamicFeeError::InvalidFeeBounds
+    );
+
+    let pool = &mut ctx.accounts.pool;
+    pool.dynamic_fee_enabled = enabled;
+    pool.min_dynamic_fee = min_fee;
+    pool.max_dynamic_fee = max_fee;
+
+    emit!(DynamicFeeUpdated {
+        pool: pool.key(),
+        enabled,
+        min_fee,
+        max_fee,
+    });
+
+    Ok(())
+}
+
+#[error_code]
+pub enum DynamicFeeError {
+    #[msg("Invalid dynamic fee bounds")]
+    InvalidFeeBounds,
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use crate::{events::FeesUpdated, state::Amm};
+
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    #[account(
+        mut,
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+        has_one = admin,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    pub admin: Signer<'info>,
+}
+
+pub fn set_fees(
+    ctx: Context<SetFees>,
+    fee_tiers: Vec<u16>,
+    protocol_fee_percentage: u16,
+) -> Result<()> {
+    let amm = &mut ctx.accounts.amm;
+    amm.set_fees(fee_tiers, protocol_fee_percentage)?;
+
+    emit!(FeesUpdated {
+        amm: amm.key(),
+        fee_tiers: amm.fee_tiers.clone(),
+        protocol_fee_percentage,
+    });
+
+    Ok(())
+}
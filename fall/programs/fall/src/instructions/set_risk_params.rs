@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use crate::{
+    events::RiskParamsUpdated,
+    state::{Amm, Pool},
+};
+
+#[derive(Accounts)]
+pub struct SetRiskParams<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+        has_one = admin,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub lend_mint: Box<Account<'info, Mint>>,
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    pub admin: Signer<'info>,
+}
+
+pub fn set_risk_params(
+    ctx: Context<SetRiskParams>,
+    max_ltv: u64,
+    liquidation_threshold: u64,
+    liquidation_bonus: u64,
+) -> Result<()> {
+    let lend_a = ctx.accounts.pool.is_lend_a(
+        &ctx.accounts.lend_mint.key(),
+        &ctx.accounts.collateral_mint.key(),
+    )?;
+    ctx.accounts.pool
+        .lending_market_mut(lend_a)
+        .set_risk_params(max_ltv, liquidation_threshold, liquidation_bonus)?;
+
+    emit!(RiskParamsUpdated {
+        pool: ctx.accounts.pool.key(),
+        lend_mint: ctx.accounts.lend_mint.key(),
+        max_ltv,
+        liquidation_threshold,
+        liquidation_bonus,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use crate::{
+    constants::MAX_TWAP_WINDOW,
+    events::TwapWindowUpdated,
+    state::{Amm, Pool},
+};
+
+#[derive(Accounts)]
+pub struct SetTwapWindow<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+        has_one = admin,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub admin: Signer<'info>,
+}
+
+pub fn set_twap_window(ctx: Context<SetTwapWindow>, twap_window: u64) -> Result<()> {
+    // The observation ring buffer has to span the whole window for the TWAP to be available
+    require!(
+        twap_window > 0 && twap_window <= MAX_TWAP_WINDOW,
+        TwapError::InvalidTwapWindow
+    );
+    ctx.accounts.pool.twap_window = twap_window;
+
+    emit!(TwapWindowUpdated {
+        pool: ctx.accounts.pool.key(),
+        twap_window,
+    });
+
+    Ok(())
+}
+
+#[error_code]
+pub enum TwapError {
+    #[msg("Invalid TWAP window")]
+    InvalidTwapWindow,
+}
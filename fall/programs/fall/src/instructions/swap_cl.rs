@@ -0,0 +1,221 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{self, Mint, Token, TokenAccount, Transfer},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, CL_POOL_SEED},
+    events::ClSwapEvent,
+    instructions::{check_expiry, ClPoolError},
+    state::{Amm, ClPool, ClTick},
+    tick_math::{compute_swap_step, sqrt_price_at_tick, tick_at_sqrt_price},
+};
+
+#[derive(Accounts)]
+pub struct SwapCl<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            cl_pool.amm.as_ref(),
+            cl_pool.mint_a.key().as_ref(),
+            cl_pool.mint_b.key().as_ref(),
+            cl_pool.liquidity_fee.to_le_bytes().as_ref(),
+            CL_POOL_SEED,
+        ],
+        bump,
+        has_one = amm,
+        has_one = mint_a,
+        has_one = mint_b,
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            cl_pool.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub cl_pool_authority: AccountInfo<'info>,
+
+    pub mint_a: Box<Account<'info, Mint>>,
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = cl_pool_authority,
+    )]
+    pub cl_pool_account_b: Box<Account<'info, TokenAccount>>,
+
+    pub trader: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = trader,
+    )]
+    pub trader_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = trader,
+    )]
+    pub trader_account_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Swap `input_amount` of token A (`a_to_b`) or token B through the concentrated liquidity pool.
+/// `remaining_accounts` holds the tick accounts of the initialized ticks the swap crosses, in order.
+/// The swap stops early at the end of the usable tick range and only takes the input it used.
+pub fn swap_cl<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapCl<'info>>,
+    a_to_b: bool,
+    input_amount: u64,
+    min_output_amount: u64,
+    expiry_slot: Option<u64>,
+) -> Result<()> {
+    // Reject stale transactions
+    check_expiry(expiry_slot)?;
+
+    // Zero amount check
+    require!(input_amount > 0, ClPoolError::InvalidInput);
+
+    let pool_key = ctx.accounts.cl_pool.key();
+    let protocol_fee_percentage = ctx.accounts.amm.protocol_fee_percentage;
+    let pool: &mut ClPool = &mut ctx.accounts.cl_pool;
+    let mut tick_accounts = ctx.remaining_accounts.iter();
+    let mut amount_remaining = input_amount;
+    let mut output_amount: u64 = 0;
+
+    while amount_remaining > 0 {
+        let (next_tick, initialized) = pool.next_initialized_tick(a_to_b);
+        let sqrt_price_target = sqrt_price_at_tick(next_tick)?;
+        let step = compute_swap_step(
+            pool.sqrt_price,
+            sqrt_price_target,
+            pool.liquidity,
+            amount_remaining,
+            pool.liquidity_fee,
+        )?;
+
+        amount_remaining -= step.amount_in + step.fee_amount;
+        output_amount = output_amount
+            .checked_add(step.amount_out)
+            .ok_or(ClPoolError::MathOverflow)?;
+        pool.accrue_fee(a_to_b, step.fee_amount, protocol_fee_percentage)?;
+        pool.sqrt_price = step.sqrt_price_next;
+
+        if step.sqrt_price_next != sqrt_price_target {
+            pool.tick_current = tick_at_sqrt_price(pool.sqrt_price)?;
+            continue;
+        }
+
+        // Reached the next tick: cross it, or stop at the end of the usable range
+        pool.tick_current = if a_to_b { next_tick - 1 } else { next_tick };
+        if !initialized {
+            break;
+        }
+        let tick_info = tick_accounts.next().ok_or(ClPoolError::InvalidTickAccount)?;
+        let mut tick = Account::<ClTick>::try_from(tick_info)?;
+        require!(
+            tick.cl_pool == pool_key && tick.tick_index == next_tick,
+            ClPoolError::InvalidTickAccount
+        );
+        let liquidity_net = tick.cross(pool);
+        pool.apply_liquidity_delta(if a_to_b { -liquidity_net } else { liquidity_net })?;
+
+        // Accounts from remaining_accounts are not persisted automatically
+        tick.exit(ctx.program_id)?;
+    }
+
+    let used_input_amount = input_amount - amount_remaining;
+    require!(output_amount > 0, ClPoolError::OutputTooSmall);
+    require!(output_amount >= min_output_amount, ClPoolError::ExcessiveSlippage);
+
+    // Transfer tokens
+    let (trader_account_in, pool_account_in, pool_account_out, trader_account_out) = if a_to_b {
+        (
+            &ctx.accounts.trader_account_a,
+            &ctx.accounts.cl_pool_account_a,
+            &ctx.accounts.cl_pool_account_b,
+            &ctx.accounts.trader_account_b,
+        )
+    } else {
+        (
+            &ctx.accounts.trader_account_b,
+            &ctx.accounts.cl_pool_account_b,
+            &ctx.accounts.cl_pool_account_a,
+            &ctx.accounts.trader_account_a,
+        )
+    };
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: trader_account_in.to_account_info(),
+                to: pool_account_in.to_account_info(),
+                authority: ctx.accounts.trader.to_account_info(),
+            },
+        ),
+        used_input_amount,
+    )?;
+
+    let authority_seeds = &[
+        &pool_key.to_bytes(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.cl_pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: pool_account_out.to_account_info(),
+                to: trader_account_out.to_account_info(),
+                authority: ctx.accounts.cl_pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        output_amount,
+    )?;
+
+    let pool = &ctx.accounts.cl_pool;
+    emit!(ClSwapEvent {
+        cl_pool: pool_key,
+        trader: ctx.accounts.trader.key(),
+        a_to_b,
+        amount_in: used_input_amount,
+        amount_out: output_amount,
+        sqrt_price: pool.sqrt_price,
+        tick_current: pool.tick_current,
+        liquidity: pool.liquidity,
+    });
+
+    Ok(())
+}
@@ -4,7 +4,10 @@ use anchor_spl::{
     token::{self, Mint, Token, TokenAccount, Transfer},
 };
 use crate::{
-    constants::{AUTHORITY_SEED, PERCENT_BASE},
+    constants::AUTHORITY_SEED,
+    curve,
+    events::SwapEvent,
+    instructions::{calculate_swap_output, check_expiry},
     state::{Amm, Pool},
 };
 
@@ -19,10 +22,12 @@ pub struct SwapExactTokensForTokens<'info> {
     pub amm: Box<Account<'info, Amm>>,
 
     #[account(
+        mut,
         seeds = [
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
             pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
         ],
         bump,
         has_one = mint_a,
@@ -36,6 +41,7 @@ pub struct SwapExactTokensForTokens<'info> {
             pool.amm.as_ref(),
             mint_a.key().as_ref(),
             mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
             AUTHORITY_SEED,
         ],
         bump,
@@ -90,15 +96,17 @@ pub fn swap_exact_tokens_for_tokens<'info>(
     swap_a: bool,
     input_amount: u64,
     min_output_amount: u64,
+    expiry_slot: Option<u64>,
 ) -> Result<()> {
+    // Reject stale transactions
+    check_expiry(expiry_slot)?;
+
     // Zero amount check
     require!(input_amount > 0, SwapError::InvalidInput);
     
     // Check pool is not empty
-    require!(
-        ctx.accounts.pool_account_a.amount > 0 && ctx.accounts.pool_account_b.amount > 0,
-        SwapError::EmptyPool
-    );
+    let (reserve_a, reserve_b) = ctx.accounts.pool.reserves();
+    require!(reserve_a > 0 && reserve_b > 0, SwapError::EmptyPool);
 
     // Prevent depositing assets the depositor does not own
     if swap_a {
@@ -107,45 +115,18 @@ pub fn swap_exact_tokens_for_tokens<'info>(
         require!(ctx.accounts.trader_account_b.amount >= input_amount, SwapError::InsufficientBalance);
     }
 
-    let pool_a = &ctx.accounts.pool_account_a;
-    let pool_b = &ctx.accounts.pool_account_b;
-
-    // Calculate raw output amount using u128 for better precision
-    let raw_output = if swap_a {
-        let new_pool_a: u128 = (pool_a.amount as u128)
-            .checked_add(input_amount as u128)
-            .ok_or(SwapError::MathOverflow)?;
-        let k = (pool_a.amount as u128)
-            .checked_mul(pool_b.amount as u128)
-            .ok_or(SwapError::MathOverflow)?;
-        (pool_b.amount as u128)
-            .checked_sub(k.checked_div(new_pool_a).ok_or(SwapError::MathOverflow)?)
-            .ok_or(SwapError::MathOverflow)?
+    // Calculate the output on the pool's curve, net of the liquidity fee
+    let (reserve_in, reserve_out) = if swap_a {
+        (reserve_a, reserve_b)
     } else {
-        let new_pool_b: u128 = (pool_b.amount as u128)
-            .checked_add(input_amount as u128)
-            .ok_or(SwapError::MathOverflow)?;
-        let k = (pool_a.amount as u128)
-            .checked_mul(pool_b.amount as u128)
-            .ok_or(SwapError::MathOverflow)?;
-        (pool_a.amount as u128)
-            .checked_sub(k.checked_div(new_pool_b).ok_or(SwapError::MathOverflow)?)
-            .ok_or(SwapError::MathOverflow)?
+        (reserve_b, reserve_a)
     };
-
-    // Apply fee on output amount
-    let fee_amount = raw_output
-        .checked_mul(ctx.accounts.amm.liquidity_fee as u128)
-        .ok_or(SwapError::MathOverflow)?
-        .checked_div(PERCENT_BASE as u128)
-        .ok_or(SwapError::MathOverflow)?;
-    let output = raw_output
-        .checked_sub(fee_amount)
-        .ok_or(SwapError::MathOverflow)?;
-
-    // Check output bounds
-    require!(output <= u64::MAX as u128, SwapError::MathOverflow);
-    let output = output as u64;
+    let (output, fee_amount) = calculate_swap_output(
+        &ctx.accounts.pool,
+        reserve_in,
+        reserve_out,
+        input_amount,
+    )?;
 
     // Slippage check
     require!(output >= min_output_amount, SwapError::ExcessiveSlippage);
@@ -156,13 +137,14 @@ pub fn swap_exact_tokens_for_tokens<'info>(
         &ctx.accounts.pool.amm.to_bytes(),
         &ctx.accounts.mint_a.key().to_bytes(),
         &ctx.accounts.mint_b.key().to_bytes(),
+        &ctx.accounts.pool.liquidity_fee.to_le_bytes()[..],
         AUTHORITY_SEED,
         &[authority_bump],
     ];
     let signer_seeds = &[&authority_seeds[..]];
 
-    // Compute the invariant before the trade
-    let old_invariant = (pool_a.amount as u128) * (pool_b.amount as u128);
+    // Accumulate the pre-trade price before the reserves move
+    ctx.accounts.pool.update_price_accumulators()?;
 
     if swap_a {
         token::transfer(
@@ -230,20 +212,38 @@ pub fn swap_exact_tokens_for_tokens<'info>(
             .ok_or(SwapError::MathOverflow)?;
     }
 
-    // Verify the invariant
-    let new_invariant = if swap_a {
-        ((ctx.accounts.pool_account_a.amount + input_amount) as u128)
-            .checked_mul((ctx.accounts.pool_account_b.amount - output) as u128)
-            .ok_or(SwapError::MathOverflow)?
-    } else {
-        ((ctx.accounts.pool_account_a.amount - output) as u128)
-            .checked_mul((ctx.accounts.pool_account_b.amount + input_amount) as u128)
-            .ok_or(SwapError::MathOverflow)?
-    };
+    // Take the protocol's share of the fee out of the reserves
+    let protocol_fee = ctx.accounts.pool.accrue_protocol_fee(
+        !swap_a,
+        fee_amount,
+        ctx.accounts.amm.protocol_fee_percentage,
+    )?;
+
+    // Verify the invariant on the pool's curve, it should not decrease (the fees stay in the pool)
+    let new_reserve_in = reserve_in
+        .checked_add(input_amount)
+        .ok_or(SwapError::MathOverflow)?;
+    let new_reserve_out = reserve_out
+        .checked_sub(output + protocol_fee)
+        .ok_or(SwapError::MathOverflow)?;
+    let pool = &ctx.accounts.pool;
+    require!(
+        curve::invariant_holds(pool.curve_type, pool.amp, reserve_in, reserve_out, new_reserve_in, new_reserve_out)?,
+        SwapError::InvariantViolated
+    );
+
+    emit!(SwapEvent {
+        pool: pool.key(),
+        trader: ctx.accounts.trader.key(),
+        mint_in: if swap_a { pool.mint_a } else { pool.mint_b },
+        amount_in: input_amount,
+        amount_out: output,
+        fee_amount,
+        protocol_fee,
+        reserve_a: pool.token_a_amount,
+        reserve_b: pool.token_b_amount,
+    });
 
-    // New invariant should be less than old invariant (because of fees)
-    require!(new_invariant <= old_invariant, SwapError::InvariantViolated);
-    
     Ok(())
 }
 
@@ -0,0 +1,270 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::{self, Token, TokenAccount, Transfer},
+};
+use crate::{
+    constants::AUTHORITY_SEED,
+    curve,
+    events::SwapEvent,
+    instructions::{calculate_swap_output, check_expiry},
+    state::{Amm, Pool},
+};
+
+/// Accounts per hop in `remaining_accounts`:
+/// [pool, pool_authority, pool_account_in, pool_account_out, trader_account_out]
+pub const ROUTE_HOP_ACCOUNTS: usize = 5;
+
+#[derive(Accounts)]
+pub struct SwapRoute<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    pub trader: Signer<'info>,
+
+    /// The trader's account for the first input token
+    #[account(
+        mut,
+        token::authority = trader,
+    )]
+    pub trader_account_in: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn swap_route<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+    input_amount: u64,
+    min_output_amount: u64,
+    expiry_slot: Option<u64>,
+) -> Result<()> {
+    // Reject stale transactions
+    check_expiry(expiry_slot)?;
+
+    // Zero amount check
+    require!(input_amount > 0, SwapRouteError::InvalidInput);
+
+    let hops = ctx.remaining_accounts.chunks_exact(ROUTE_HOP_ACCOUNTS);
+    require!(
+        !ctx.remaining_accounts.is_empty() && hops.remainder().is_empty(),
+        SwapRouteError::InvalidRoute
+    );
+
+    // Prevent depositing assets the trader does not own
+    require!(
+        ctx.accounts.trader_account_in.amount >= input_amount,
+        SwapRouteError::InsufficientBalance
+    );
+
+    let mut trader_account_in = ctx.accounts.trader_account_in.to_account_info();
+    let mut mint_in = ctx.accounts.trader_account_in.mint;
+    let mut amount_in = input_amount;
+
+    for hop in hops {
+        let (output, mint_out) = swap_hop(&ctx, hop, &trader_account_in, mint_in, amount_in)?;
+        trader_account_in = hop[4].clone();
+        mint_in = mint_out;
+        amount_in = output;
+    }
+
+    // Slippage check on the final output only
+    require!(amount_in >= min_output_amount, SwapRouteError::ExcessiveSlippage);
+
+    Ok(())
+}
+
+/// Validate one hop of the route, swap `amount_in` of `mint_in` through its pool
+/// and return the output amount and mint
+#[inline(never)]
+fn swap_hop<'info>(
+    ctx: &Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+    hop: &'info [AccountInfo<'info>],
+    trader_account_in: &AccountInfo<'info>,
+    mint_in: Pubkey,
+    amount_in: u64,
+) -> Result<(u64, Pubkey)> {
+    let amm = &ctx.accounts.amm;
+
+    // The pool must belong to this AMM and live at its canonical address
+    let mut pool = Account::<Pool>::try_from(&hop[0])?;
+    require_keys_eq!(pool.amm, amm.key(), SwapRouteError::InvalidPool);
+    let (pool_key, _) = Pubkey::find_program_address(
+        &[
+            pool.amm.as_ref(),
+            pool.mint_a.as_ref(),
+            pool.mint_b.as_ref(),
+            &pool.liquidity_fee.to_le_bytes(),
+        ],
+        ctx.program_id,
+    );
+    require_keys_eq!(pool_key, pool.key(), SwapRouteError::InvalidPool);
+
+    let (pool_authority_key, authority_bump) = Pubkey::find_program_address(
+        &[
+            pool.amm.as_ref(),
+            pool.mint_a.as_ref(),
+            pool.mint_b.as_ref(),
+            &pool.liquidity_fee.to_le_bytes(),
+            AUTHORITY_SEED,
+        ],
+        ctx.program_id,
+    );
+    require_keys_eq!(pool_authority_key, hop[1].key(), SwapRouteError::InvalidPool);
+
+    // The input token must be one side of the pool
+    let swap_a = if mint_in == pool.mint_a {
+        true
+    } else if mint_in == pool.mint_b {
+        false
+    } else {
+        return err!(SwapRouteError::InvalidRoute);
+    };
+    let mint_out = if swap_a { pool.mint_b } else { pool.mint_a };
+
+    // The vaults must be the pool authority's associated token accounts
+    require_keys_eq!(
+        get_associated_token_address(&pool_authority_key, &mint_in),
+        hop[2].key(),
+        SwapRouteError::InvalidPool
+    );
+    require_keys_eq!(
+        get_associated_token_address(&pool_authority_key, &mint_out),
+        hop[3].key(),
+        SwapRouteError::InvalidPool
+    );
+
+    // The output account must be the trader's
+    let trader_account_out = Account::<TokenAccount>::try_from(&hop[4])?;
+    require_keys_eq!(trader_account_out.owner, ctx.accounts.trader.key(), SwapRouteError::InvalidRoute);
+    require_keys_eq!(trader_account_out.mint, mint_out, SwapRouteError::InvalidRoute);
+
+    let (reserve_a, reserve_b) = pool.reserves();
+    let (reserve_in, reserve_out) = if swap_a {
+        (reserve_a, reserve_b)
+    } else {
+        (reserve_b, reserve_a)
+    };
+
+    // Check pool is not empty
+    require!(reserve_in > 0 && reserve_out > 0, SwapRouteError::EmptyPool);
+    let (output, fee_amount) = calculate_swap_output(
+        &pool,
+        reserve_in,
+        reserve_out,
+        amount_in,
+    )?;
+    require!(output > 0, SwapRouteError::OutputTooSmall);
+
+    // Accumulate the pre-trade price before the reserves move
+    pool.update_price_accumulators()?;
+
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: trader_account_in.clone(),
+                to: hop[2].clone(),
+                authority: ctx.accounts.trader.to_account_info(),
+            },
+        ),
+        amount_in,
+    )?;
+
+    let authority_seeds = &[
+        pool.amm.as_ref(),
+        pool.mint_a.as_ref(),
+        pool.mint_b.as_ref(),
+        &pool.liquidity_fee.to_le_bytes(),
+        AUTHORITY_SEED,
+        &[authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: hop[3].clone(),
+                to: hop[4].clone(),
+                authority: hop[1].clone(),
+            },
+            signer_seeds,
+        ),
+        output,
+    )?;
+
+    // Update pool state
+    if swap_a {
+        pool.token_a_amount = pool.token_a_amount
+            .checked_add(amount_in)
+            .ok_or(SwapRouteError::MathOverflow)?;
+        pool.token_b_amount = pool.token_b_amount
+            .checked_sub(output)
+            .ok_or(SwapRouteError::MathOverflow)?;
+    } else {
+        pool.token_a_amount = pool.token_a_amount
+            .checked_sub(output)
+            .ok_or(SwapRouteError::MathOverflow)?;
+        pool.token_b_amount = pool.token_b_amount
+            .checked_add(amount_in)
+            .ok_or(SwapRouteError::MathOverflow)?;
+    }
+
+    // Take the protocol's share of the fee out of the reserves
+    let protocol_fee = pool.accrue_protocol_fee(!swap_a, fee_amount, amm.protocol_fee_percentage)?;
+
+    // Verify the invariant on the pool's curve, it should not decrease (the fees stay in the pool)
+    let new_reserve_in = reserve_in
+        .checked_add(amount_in)
+        .ok_or(SwapRouteError::MathOverflow)?;
+    let new_reserve_out = reserve_out
+        .checked_sub(output + protocol_fee)
+        .ok_or(SwapRouteError::MathOverflow)?;
+    require!(
+        curve::invariant_holds(pool.curve_type, pool.amp, reserve_in, reserve_out, new_reserve_in, new_reserve_out)?,
+        SwapRouteError::InvariantViolated
+    );
+
+    emit!(SwapEvent {
+        pool: pool.key(),
+        trader: ctx.accounts.trader.key(),
+        mint_in,
+        amount_in,
+        amount_out: output,
+        fee_amount,
+        protocol_fee,
+        reserve_a: pool.token_a_amount,
+        reserve_b: pool.token_b_amount,
+    });
+
+    // Accounts from remaining_accounts are not persisted automatically
+    pool.exit(ctx.program_id)?;
+
+    Ok((output, mint_out))
+}
+
+#[error_code]
+pub enum SwapRouteError {
+    #[msg("Invalid input amount")]
+    InvalidInput,
+    #[msg("Invalid route")]
+    InvalidRoute,
+    #[msg("Invalid pool accounts")]
+    InvalidPool,
+    #[msg("Pool is empty")]
+    EmptyPool,
+    #[msg("Output too small")]
+    OutputTooSmall,
+    #[msg("Insufficient balance")]
+    InsufficientBalance,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Slippage tolerance exceeded")]
+    ExcessiveSlippage,
+    #[msg("Invariant violated")]
+    InvariantViolated,
+}
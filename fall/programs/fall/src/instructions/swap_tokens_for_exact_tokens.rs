@@ -0,0 +1,249 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{self, Mint, Token, TokenAccount, Transfer},
+};
+use crate::{
+    constants::AUTHORITY_SEED,
+    curve,
+    events::SwapEvent,
+    instructions::{calculate_swap_input, check_expiry},
+    instructions::SwapError,
+    state::{Amm, Pool},
+};
+
+#[derive(Accounts)]
+pub struct SwapTokensForExactTokens<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+        has_one = mint_a,
+        has_one = mint_b,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.amm.as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    pub mint_a: Box<Account<'info, Mint>>,
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = pool_authority,
+    )]
+    pub pool_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = pool_authority,
+    )]
+    pub pool_account_b: Box<Account<'info, TokenAccount>>,
+
+    pub trader: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = trader,
+    )]
+    pub trader_account_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = trader,
+    )]
+    pub trader_account_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn swap_tokens_for_exact_tokens<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapTokensForExactTokens<'info>>,
+    swap_a: bool,
+    output_amount: u64,
+    max_input_amount: u64,
+    expiry_slot: Option<u64>,
+) -> Result<()> {
+    // Reject stale transactions
+    check_expiry(expiry_slot)?;
+
+    // Zero amount check
+    require!(output_amount > 0, SwapError::InvalidInput);
+
+    // Check pool is not empty
+    let (reserve_a, reserve_b) = ctx.accounts.pool.reserves();
+    require!(reserve_a > 0 && reserve_b > 0, SwapError::EmptyPool);
+
+    // Calculate the input needed for the output on the pool's curve, with the fee taken on the output
+    let (reserve_in, reserve_out) = if swap_a {
+        (reserve_a, reserve_b)
+    } else {
+        (reserve_b, reserve_a)
+    };
+    let (input_amount, fee_amount) = calculate_swap_input(
+        &ctx.accounts.pool,
+        reserve_in,
+        reserve_out,
+        output_amount,
+    )?;
+
+    // Slippage check
+    require!(input_amount <= max_input_amount, SwapError::ExcessiveSlippage);
+
+    // Prevent depositing assets the depositor does not own
+    if swap_a {
+        require!(ctx.accounts.trader_account_a.amount >= input_amount, SwapError::InsufficientBalance);
+    } else {
+        require!(ctx.accounts.trader_account_b.amount >= input_amount, SwapError::InsufficientBalance);
+    }
+
+    // Transfer tokens
+    let authority_bump = ctx.bumps.pool_authority;
+    let authority_seeds = &[
+        &ctx.accounts.pool.amm.to_bytes(),
+        &ctx.accounts.mint_a.key().to_bytes(),
+        &ctx.accounts.mint_b.key().to_bytes(),
+        &ctx.accounts.pool.liquidity_fee.to_le_bytes()[..],
+        AUTHORITY_SEED,
+        &[authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    // Accumulate the pre-trade price before the reserves move
+    ctx.accounts.pool.update_price_accumulators()?;
+
+    if swap_a {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.trader_account_a.to_account_info(),
+                    to: ctx.accounts.pool_account_a.to_account_info(),
+                    authority: ctx.accounts.trader.to_account_info(),
+                },
+            ),
+            input_amount,
+        )?;
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_account_b.to_account_info(),
+                    to: ctx.accounts.trader_account_b.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            output_amount,
+        )?;
+
+        // Update pool state
+        ctx.accounts.pool.token_a_amount = ctx.accounts.pool.token_a_amount
+            .checked_add(input_amount)
+            .ok_or(SwapError::MathOverflow)?;
+        ctx.accounts.pool.token_b_amount = ctx.accounts.pool.token_b_amount
+            .checked_sub(output_amount)
+            .ok_or(SwapError::MathOverflow)?;
+    } else {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_account_a.to_account_info(),
+                    to: ctx.accounts.trader_account_a.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            output_amount,
+        )?;
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.trader_account_b.to_account_info(),
+                    to: ctx.accounts.pool_account_b.to_account_info(),
+                    authority: ctx.accounts.trader.to_account_info(),
+                },
+            ),
+            input_amount,
+        )?;
+
+        // Update pool state
+        ctx.accounts.pool.token_a_amount = ctx.accounts.pool.token_a_amount
+            .checked_sub(output_amount)
+            .ok_or(SwapError::MathOverflow)?;
+        ctx.accounts.pool.token_b_amount = ctx.accounts.pool.token_b_amount
+            .checked_add(input_amount)
+            .ok_or(SwapError::MathOverflow)?;
+    }
+
+    // Take the protocol's share of the fee out of the reserves
+    let protocol_fee = ctx.accounts.pool.accrue_protocol_fee(
+        !swap_a,
+        fee_amount,
+        ctx.accounts.amm.protocol_fee_percentage,
+    )?;
+
+    // Verify the invariant on the pool's curve, it should not decrease (the fees stay in the pool)
+    let new_reserve_in = reserve_in
+        .checked_add(input_amount)
+        .ok_or(SwapError::MathOverflow)?;
+    let new_reserve_out = reserve_out
+        .checked_sub(output_amount + protocol_fee)
+        .ok_or(SwapError::MathOverflow)?;
+    let pool = &ctx.accounts.pool;
+    require!(
+        curve::invariant_holds(pool.curve_type, pool.amp, reserve_in, reserve_out, new_reserve_in, new_reserve_out)?,
+        SwapError::InvariantViolated
+    );
+
+    emit!(SwapEvent {
+        pool: pool.key(),
+        trader: ctx.accounts.trader.key(),
+        mint_in: if swap_a { pool.mint_a } else { pool.mint_b },
+        amount_in: input_amount,
+        amount_out: output_amount,
+        fee_amount,
+        protocol_fee,
+        reserve_a: pool.token_a_amount,
+        reserve_b: pool.token_b_amount,
+    });
+
+    Ok(())
+}
@@ -1,28 +1,69 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, MintTo};
+use crate::{
+    constants::PERCENT_BASE,
+    curve,
+    state::Pool,
+};
 
+/// Output for `input_amount` on the pool's curve, with the pool's swap fee taken on the output.
+/// Returns `(output, fee_amount)`
 #[inline(never)]
-pub fn mint_and_freeze_token<'info>(
-    token_program: &Program<'info, Token>,
-    mint: &Box<Account<'info, Mint>>,
-    recipient: &Box<Account<'info, TokenAccount>>,
-    authority: &AccountInfo<'info>,
-    signer_seeds: &[&[&[u8]]],
-    amount: u64,
-) -> Result<()> {
-    // 1. Mint tokens
-    token::mint_to(
-        CpiContext::new_with_signer(
-            token_program.to_account_info(),
-            MintTo {
-                mint: mint.to_account_info(),
-                to: recipient.to_account_info(),
-                authority: authority.to_account_info(),
-            },
-            signer_seeds,
-        ),
-        amount,
-    )?;
+pub fn calculate_swap_output(
+    pool: &Pool,
+    reserve_in: u64,
+    reserve_out: u64,
+    input_amount: u64,
+) -> Result<(u64, u64)> {
+    // Calculate raw output amount, rounding down in the pool's favour
+    let raw_output = curve::swap_output(pool.curve_type, pool.amp, reserve_in, reserve_out, input_amount)?;
+    apply_swap_fee(raw_output, pool.swap_fee()?)
+}
+
+/// Take the swap fee (10000 = 100%) out of a raw output. Returns `(output, fee_amount)`
+pub fn apply_swap_fee(raw_output: u128, swap_fee: u16) -> Result<(u64, u64)> {
+    let fee_amount = raw_output
+        .checked_mul(swap_fee as u128)
+        .ok_or(UtilsError::CalculationError)?
+        .checked_div(PERCENT_BASE as u128)
+        .ok_or(UtilsError::CalculationError)?;
+    let output = raw_output
+        .checked_sub(fee_amount)
+        .ok_or(UtilsError::CalculationError)?;
+
+    // Check output bounds
+    require!(output <= u64::MAX as u128, UtilsError::CalculationError);
+    Ok((output as u64, fee_amount as u64))
+}
+
+/// Smallest input that yields at least `output_amount` on the pool's curve after the pool's swap fee
+/// is taken on the output. Returns `(input, fee_amount)`
+#[inline(never)]
+pub fn calculate_swap_input(
+    pool: &Pool,
+    reserve_in: u64,
+    reserve_out: u64,
+    output_amount: u64,
+) -> Result<(u64, u64)> {
+    // Gross up the output by the fee, rounding up
+    let fee_base = (PERCENT_BASE as u128)
+        .checked_sub(pool.swap_fee()? as u128)
+        .ok_or(UtilsError::CalculationError)?;
+    let raw_output = (output_amount as u128)
+        .checked_mul(PERCENT_BASE as u128)
+        .ok_or(UtilsError::CalculationError)?
+        .div_ceil(fee_base);
+
+    // Input that keeps the invariant for the raw output, rounding up
+    let input = curve::swap_input(pool.curve_type, pool.amp, reserve_in, reserve_out, raw_output)?;
+    require!(input <= u64::MAX as u128, UtilsError::CalculationError);
+    Ok((input as u64, (raw_output - output_amount as u128) as u64))
+}
+
+/// Reject the transaction once the current slot is past `expiry_slot`, if one is given
+pub fn check_expiry(expiry_slot: Option<u64>) -> Result<()> {
+    if let Some(expiry_slot) = expiry_slot {
+        require!(Clock::get()?.slot <= expiry_slot, UtilsError::TransactionExpired);
+    }
     Ok(())
 }
 
@@ -30,4 +71,6 @@ pub fn mint_and_freeze_token<'info>(
 pub enum UtilsError {
     #[msg("Calculation error")]
     CalculationError,
+    #[msg("Transaction expired")]
+    TransactionExpired,
 }
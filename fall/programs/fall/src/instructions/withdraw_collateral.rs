@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::constants::{ LENDING_AUTHORITY_SEED, BORROW_POSITION_SEED };
+use crate::events::CollateralWithdrawn;
+use crate::state::{BorrowPosition, Pool};
+
+#[derive(Accounts)]
+pub struct WithdrawCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// 出借的代币
+    pub lend_mint: Box<Account<'info, Mint>>,
+    /// 抵押品代币
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.key().as_ref(),
+            LENDING_AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub lending_pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = lending_pool_authority,
+    )]
+    pub lending_pool_collateral_token: Box<Account<'info, TokenAccount>>,
+
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = borrower,
+    )]
+    pub borrower_collateral_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.key().as_ref(),
+            lend_mint.key().as_ref(),
+            borrower.key().as_ref(),
+            BORROW_POSITION_SEED,
+        ],
+        bump,
+    )]
+    pub borrow_position: Box<Account<'info, BorrowPosition>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn withdraw_collateral(
+    ctx: Context<WithdrawCollateral>,
+    collateral_amount: u64,  // 取回的 collateral token 数量
+) -> Result<()> {
+    require!(
+        collateral_amount > 0 && collateral_amount <= ctx.accounts.borrow_position.collateral,
+        WithdrawCollateralError::InvalidAmount
+    );
+
+    // 1. 计息后检查剩余抵押品按TWAP价格仍满足最大借款价值比
+    let lend_a = ctx.accounts.pool.is_lend_a(
+        &ctx.accounts.lend_mint.key(),
+        &ctx.accounts.collateral_mint.key(),
+    )?;
+    let pool_key = ctx.accounts.pool.key();
+    ctx.accounts.pool.accrue_interest(pool_key, lend_a)?;
+    let debt = ctx.accounts.borrow_position
+        .current_debt(ctx.accounts.pool.lending_market(lend_a).borrow_index)?;
+    let remaining_collateral = ctx.accounts.borrow_position.collateral - collateral_amount;
+    if debt > 0 {
+        let remaining_collateral_value = ctx.accounts.pool
+            .calculate_collateral_twap_value(lend_a, remaining_collateral)?;
+        require!(
+            ctx.accounts.pool.lending_market(lend_a).check_max_ltv(remaining_collateral_value, debt)?,
+            WithdrawCollateralError::InsufficientCollateral
+        );
+    }
+
+    // 2. 转移抵押物（collateral token）给借款人
+    let authority_seeds = &[
+        &ctx.accounts.pool.key().to_bytes(),
+        LENDING_AUTHORITY_SEED,
+        &[ctx.bumps.lending_pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.lending_pool_collateral_token.to_account_info(),
+                to: ctx.accounts.borrower_collateral_token.to_account_info(),
+                authority: ctx.accounts.lending_pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        collateral_amount,
+    )?;
+
+    // 3. 更新 borrow position
+    ctx.accounts.borrow_position.collateral = remaining_collateral;
+    let lending_market = ctx.accounts.pool.lending_market_mut(lend_a);
+    lending_market.total_collateral = lending_market.total_collateral.saturating_sub(collateral_amount);
+
+    emit!(CollateralWithdrawn {
+        pool: pool_key,
+        lend_mint: ctx.accounts.lend_mint.key(),
+        borrower: ctx.accounts.borrower.key(),
+        amount: collateral_amount,
+        collateral: remaining_collateral,
+    });
+
+    Ok(())
+}
+
+#[error_code]
+pub enum WithdrawCollateralError {
+    #[msg("Invalid collateral amount")]
+    InvalidAmount,
+    #[msg("Remaining collateral exceeds the max LTV")]
+    InsufficientCollateral,
+}
@@ -3,9 +3,11 @@ use anchor_spl::{
     associated_token::AssociatedToken,
     token::{self, Burn, Mint, Token, TokenAccount, Transfer},
 };
-use fixed::types::I64F64;
 use crate::{
     constants::{AUTHORITY_SEED, LIQUIDITY_SEED, MINIMUM_LIQUIDITY},
+    events::LiquidityRemoved,
+    instructions::check_expiry,
+    math::mul_div_floor,
     state::Pool,
 };
 
@@ -13,10 +15,12 @@ use crate::{
 #[derive(Accounts)]
 pub struct WithdrawLiquidity<'info> {
     #[account(
+        mut,
         seeds = [
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
             pool.mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
         ],
         bump,
         has_one = mint_a,
@@ -30,6 +34,7 @@ pub struct WithdrawLiquidity<'info> {
             pool.amm.as_ref(),
             mint_a.key().as_ref(),
             mint_b.key().as_ref(),
+            pool.liquidity_fee.to_le_bytes().as_ref(),
             AUTHORITY_SEED,
         ],
         bump,
@@ -102,27 +107,48 @@ pub struct WithdrawLiquidity<'info> {
 }
 
 
-pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, amount: u64) -> Result<()> {
+pub fn withdraw_liquidity(
+    ctx: Context<WithdrawLiquidity>,
+    amount: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+    expiry_slot: Option<u64>,
+) -> Result<()> {
+    // Reject stale transactions
+    check_expiry(expiry_slot)?;
+
     let authority_bump = ctx.bumps.pool_authority;
     let authority_seeds = &[
         &ctx.accounts.pool.amm.to_bytes(),
         &ctx.accounts.mint_a.key().to_bytes(),
         &ctx.accounts.mint_b.key().to_bytes(),
+        &ctx.accounts.pool.liquidity_fee.to_le_bytes()[..],
         AUTHORITY_SEED,
         &[authority_bump],
     ];
     let signer_seeds = &[&authority_seeds[..]];
 
+    // Protocol fees and donations held in the vaults are not part of the reserves
+    let (reserve_a, reserve_b) = ctx.accounts.pool.reserves();
+
+    // Amounts of each token the liquidity is worth
+    let total_liquidity = ctx.accounts.liquidity_mint.supply
+        .checked_add(MINIMUM_LIQUIDITY)
+        .ok_or(WithdrawError::NumberOverflow)? as u128;
+    let amount_a = mul_div_floor(amount as u128, reserve_a as u128, total_liquidity)
+        .and_then(|amount_a| u64::try_from(amount_a).ok())
+        .ok_or(WithdrawError::NumberOverflow)?;
+    let amount_b = mul_div_floor(amount as u128, reserve_b as u128, total_liquidity)
+        .and_then(|amount_b| u64::try_from(amount_b).ok())
+        .ok_or(WithdrawError::NumberOverflow)?;
+
+    // Slippage check
+    require!(
+        amount_a >= min_amount_a && amount_b >= min_amount_b,
+        WithdrawError::ExcessiveSlippage
+    );
+
     // Transfer tokens from the pool
-    let amount_a = I64F64::from_num(amount)
-        .checked_mul(I64F64::from_num(ctx.accounts.pool_account_a.amount))
-        .unwrap()
-        .checked_div(I64F64::from_num(
-            ctx.accounts.liquidity_mint.supply + MINIMUM_LIQUIDITY,
-        ))
-        .unwrap()
-        .floor()
-        .to_num::<u64>();
     token::transfer(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
@@ -135,16 +161,6 @@ pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, amount: u64) -> Resul
         ),
         amount_a,
     )?;
-
-    let amount_b = I64F64::from_num(amount)
-        .checked_mul(I64F64::from_num(ctx.accounts.pool_account_b.amount))
-        .unwrap()
-        .checked_div(I64F64::from_num(
-            ctx.accounts.liquidity_mint.supply + MINIMUM_LIQUIDITY,
-        ))
-        .unwrap()
-        .floor()
-        .to_num::<u64>();
     token::transfer(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
@@ -158,6 +174,15 @@ pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, amount: u64) -> Resul
         amount_b,
     )?;
 
+    // Update pool state
+    ctx.accounts.pool.update_price_accumulators()?;
+    ctx.accounts.pool.token_a_amount = ctx.accounts.pool.token_a_amount
+        .checked_sub(amount_a)
+        .ok_or(WithdrawError::NumberOverflow)?;
+    ctx.accounts.pool.token_b_amount = ctx.accounts.pool.token_b_amount
+        .checked_sub(amount_b)
+        .ok_or(WithdrawError::NumberOverflow)?;
+
     // Burn the liquidity tokens
     // It will fail if the amount is invalid
     token::burn(
@@ -172,5 +197,23 @@ pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, amount: u64) -> Resul
         amount,
     )?;
 
+    emit!(LiquidityRemoved {
+        pool: ctx.accounts.pool.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount_a,
+        amount_b,
+        liquidity: amount,
+        reserve_a: ctx.accounts.pool.token_a_amount,
+        reserve_b: ctx.accounts.pool.token_b_amount,
+    });
+
     Ok(())
 }
+
+#[error_code]
+pub enum WithdrawError {
+    #[msg("Number overflow")]
+    NumberOverflow,
+    #[msg("Slippage tolerance exceeded")]
+    ExcessiveSlippage,
+}
@@ -1,9 +1,15 @@
-#![allow(clippy::result_large_err)]
+#![allow(clippy::result_large_err, unexpected_cfgs)]
 
 use anchor_lang::prelude::*;
 mod constants;
+mod events;
 mod instructions;
 mod state;
+mod curve;
+mod math;
+mod tick_math;
+
+use curve::CurveType;
 
 declare_id!("HRrJFxxQzPijk2VbF2ttqBtDUKQXVkytAf1cB5GzJuYB");
 
@@ -17,24 +23,72 @@ pub mod fall {
         instructions::create_amm(ctx, id)
     }
 
-    pub fn create_pool_1(ctx: Context<CreatePool1>) -> Result<()> {
-        instructions::create_pool_1(ctx)
+    pub fn set_fees(
+        ctx: Context<SetFees>,
+        fee_tiers: Vec<u16>,
+        protocol_fee_percentage: u16,
+    ) -> Result<()> {
+        instructions::set_fees(ctx, fee_tiers, protocol_fee_percentage)
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
     }
 
-    pub fn create_pool_2(ctx: Context<CreatePool2>) -> Result<()> {
-        instructions::create_pool_2(ctx)
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    pub fn create_market(
+        ctx: Context<CreateMarket>,
+        liquidity_fee: u16,
+        curve_type: CurveType,
+        amp: u64,
+        amount_a: u64,
+        amount_b: u64,
+        lending_params: LendingParams,
+    ) -> Result<()> {
+        instructions::create_market(ctx, liquidity_fee, curve_type, amp, amount_a, amount_b, lending_params)
     }
 
     pub fn deposit_liquidity(
         ctx: Context<DepositLiquidity>,
         amount_a: u64,
         amount_b: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+        min_liquidity_out: u64,
+        expiry_slot: Option<u64>,
     ) -> Result<()> {
-        instructions::deposit_liquidity(ctx, amount_a, amount_b)
+        instructions::deposit_liquidity(
+            ctx,
+            amount_a,
+            amount_b,
+            min_amount_a,
+            min_amount_b,
+            min_liquidity_out,
+            expiry_slot,
+        )
     }
 
-    pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, amount: u64) -> Result<()> {
-        instructions::withdraw_liquidity(ctx, amount)
+    pub fn deposit_single_sided(
+        ctx: Context<DepositSingleSided>,
+        deposit_a: bool,
+        amount: u64,
+        min_liquidity_out: u64,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::deposit_single_sided(ctx, deposit_a, amount, min_liquidity_out, expiry_slot)
+    }
+
+    pub fn withdraw_liquidity(
+        ctx: Context<WithdrawLiquidity>,
+        amount: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::withdraw_liquidity(ctx, amount, min_amount_a, min_amount_b, expiry_slot)
     }
 
     pub fn swap_exact_tokens_for_tokens<'info>(
@@ -42,32 +96,125 @@ pub mod fall {
         swap_a: bool,
         input_amount: u64,
         min_output_amount: u64,
+        expiry_slot: Option<u64>,
     ) -> Result<()> {
-        instructions::swap_exact_tokens_for_tokens(ctx, swap_a, input_amount, min_output_amount)
+        instructions::swap_exact_tokens_for_tokens(ctx, swap_a, input_amount, min_output_amount, expiry_slot)
+    }
+
+    pub fn swap_tokens_for_exact_tokens<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapTokensForExactTokens<'info>>,
+        swap_a: bool,
+        output_amount: u64,
+        max_input_amount: u64,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::swap_tokens_for_exact_tokens(ctx, swap_a, output_amount, max_input_amount, expiry_slot)
+    }
+
+    pub fn swap_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+        input_amount: u64,
+        min_output_amount: u64,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::swap_route(ctx, input_amount, min_output_amount, expiry_slot)
     }
 
     pub fn price(ctx: Context<Price>) -> Result<PriceResult> {
         instructions::price(ctx)    
     }
 
-    pub fn init_lending_pool_1(ctx: Context<InitLendingPool1>) -> Result<()> {
-        instructions::init_lending_pool_1(ctx)
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        instructions::collect_protocol_fees(ctx)
+    }
+
+    pub fn create_cl_pool(
+        ctx: Context<CreateClPool>,
+        liquidity_fee: u16,
+        tick_spacing: u16,
+        initial_sqrt_price: u128,
+    ) -> Result<()> {
+        instructions::create_cl_pool(ctx, liquidity_fee, tick_spacing, initial_sqrt_price)
+    }
+
+    pub fn open_cl_position(
+        ctx: Context<OpenClPosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        instructions::open_cl_position(ctx, tick_lower, tick_upper)
+    }
+
+    pub fn increase_cl_liquidity(
+        ctx: Context<IncreaseClLiquidity>,
+        liquidity: u128,
+        max_amount_a: u64,
+        max_amount_b: u64,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::increase_cl_liquidity(ctx, liquidity, max_amount_a, max_amount_b, expiry_slot)
+    }
+
+    pub fn decrease_cl_liquidity(
+        ctx: Context<DecreaseClLiquidity>,
+        liquidity: u128,
+        min_amount_a: u64,
+        min_amount_b: u64,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::decrease_cl_liquidity(ctx, liquidity, min_amount_a, min_amount_b, expiry_slot)
     }
 
-    pub fn init_lending_pool_2(ctx: Context<InitLendingPool2>) -> Result<()> {
-        instructions::init_lending_pool_2(ctx)
+    pub fn collect_cl_fees(ctx: Context<CollectClFees>) -> Result<()> {
+        instructions::collect_cl_fees(ctx)
     }
 
-    pub fn init_lending_pool_3(ctx: Context<InitLendingPool3>) -> Result<()> {
-        instructions::init_lending_pool_3(ctx)
+    pub fn close_cl_position(ctx: Context<CloseClPosition>) -> Result<()> {
+        instructions::close_cl_position(ctx)
+    }
+
+    pub fn swap_cl<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapCl<'info>>,
+        a_to_b: bool,
+        input_amount: u64,
+        min_output_amount: u64,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::swap_cl(ctx, a_to_b, input_amount, min_output_amount, expiry_slot)
+    }
+
+    pub fn collect_cl_protocol_fees(ctx: Context<CollectClProtocolFees>) -> Result<()> {
+        instructions::collect_cl_protocol_fees(ctx)
+    }
+
+    pub fn set_twap_window(ctx: Context<SetTwapWindow>, twap_window: u64) -> Result<()> {
+        instructions::set_twap_window(ctx, twap_window)
+    }
+
+    pub fn set_dynamic_fee(
+        ctx: Context<SetDynamicFee>,
+        enabled: bool,
+        min_fee: u16,
+        max_fee: u16,
+    ) -> Result<()> {
+        instructions::set_dynamic_fee(ctx, enabled, min_fee, max_fee)
+    }
+
+    pub fn set_risk_params(
+        ctx: Context<SetRiskParams>,
+        max_ltv: u64,
+        liquidation_threshold: u64,
+        liquidation_bonus: u64,
+    ) -> Result<()> {
+        instructions::set_risk_params(ctx, max_ltv, liquidation_threshold, liquidation_bonus)
     }
 
     pub fn lend(ctx: Context<Lend>, user_lending_amount: u64) -> Result<()> {
         instructions::lend(ctx, user_lending_amount)
     }
 
-    pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
-        instructions::redeem(ctx)
+    pub fn redeem(ctx: Context<Redeem>, amount: u64) -> Result<()> {
+        instructions::redeem(ctx, amount)
     }
 
     pub fn borrow(
@@ -81,13 +228,34 @@ pub mod fall {
         instructions::deposit_collateral(ctx, amount)
     }
 
+    pub fn withdraw_collateral(ctx: Context<WithdrawCollateral>, amount: u64) -> Result<()> {
+        instructions::withdraw_collateral(ctx, amount)
+    }
+
+
+    pub fn repay(ctx: Context<Repay>, amount: u64, withdraw_collateral: bool) -> Result<()> {
+        instructions::repay(ctx, amount, withdraw_collateral)
+    }
+
+    pub fn liquidate(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
+        instructions::liquidate(ctx, repay_amount)
+    }
+
+    pub fn liquidate_with_swap(
+        ctx: Context<LiquidateWithSwap>,
+        repay_amount: u64,
+        max_input_amount: u64,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::liquidate_with_swap(ctx, repay_amount, max_input_amount, expiry_slot)
+    }
 
-    pub fn repay(ctx: Context<Repay>) -> Result<()> {
-        instructions::repay(ctx)
+    pub fn flash_borrow(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+        instructions::flash_borrow(ctx, amount)
     }
 
-    pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
-        instructions::liquidate(ctx)
+    pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+        instructions::flash_repay(ctx)
     }
 
 }
@@ -0,0 +1,29 @@
+// Lints fire inside the code generated by `construct_uint!`
+#![allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+
+use uint::construct_uint;
+
+construct_uint! {
+    /// 256-bit unsigned integer for intermediate products that overflow u128
+    pub struct U256(4);
+}
+
+/// a * b / c rounded down, None on overflow or division by zero
+pub fn mul_div_floor(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    let result = U256::from(a) * U256::from(b) / U256::from(c);
+    u128::try_from(result).ok()
+}
+
+/// a * b / c rounded up, None on overflow or division by zero
+pub fn mul_div_ceil(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    let product = U256::from(a) * U256::from(b);
+    let (quotient, remainder) = product.div_mod(U256::from(c));
+    let result = if remainder.is_zero() { quotient } else { quotient + 1 };
+    u128::try_from(result).ok()
+}
@@ -1,5 +1,11 @@
 use anchor_lang::prelude::*;
-use crate::constants::{BASE_INTEREST_RATE, PERCENT_BASE, MIN_COLLATERAL_RATIO};
+use crate::curve::{self, CurveType};
+use crate::constants::{
+    PERCENT_BASE, MAX_FEE_TIERS, MAX_LIQUIDITY_FEE, SLOTS_PER_YEAR, MAX_BORROW_RATE, MAX_LIQUIDATION_BONUS,
+    OBSERVATION_CAPACITY, WAD, DYNAMIC_FEE_SENSITIVITY, MIN_TICK, MAX_TICK, TICK_BITMAP_WORDS,
+};
+use crate::math::mul_div_floor;
+use crate::events::InterestAccrued;
 
 #[account]
 #[derive(Default)]
@@ -10,16 +16,41 @@ pub struct Amm {
     /// Account that has admin authority over the AMM
     pub admin: Pubkey,
 
-    /// liquidity fee percentage: 100000 = 100%
-    pub liquidity_fee: u16, 
+    /// Approved liquidity fee tiers pools can be created with: 10000 = 100%
+    pub fee_tiers: Vec<u16>,
 
-    /// Protocol fee percentage of the liquidity fee (0-100)
-    /// e.g., 10000 means 100% of liquidity fee goes to protocol
+    /// Protocol share of each swap's liquidity fee: 10000 = 100%
     pub protocol_fee_percentage: u16,
+
+    /// Admin proposed by the current admin, takes over once it accepts
+    pub pending_admin: Pubkey,
 }
 
 impl Amm {
-    pub const LEN: usize = 8 + 32 + 32 + 2 + 2;
+    pub const LEN: usize = 8 + 32 + 32 + 4 + 2 * MAX_FEE_TIERS + 2 + 32;
+
+    /// Update the fees: fee tiers must be strictly increasing and capped at `MAX_LIQUIDITY_FEE`,
+    /// the protocol share of the liquidity fee at 100%.
+    /// Existing pools keep the tier they were created with.
+    pub fn set_fees(&mut self, fee_tiers: Vec<u16>, protocol_fee_percentage: u16) -> Result<()> {
+        require!(
+            !fee_tiers.is_empty() && fee_tiers.len() <= MAX_FEE_TIERS,
+            StateError::InvalidFee
+        );
+        require!(
+            fee_tiers.windows(2).all(|w| w[0] < w[1]),
+            StateError::InvalidFee
+        );
+        require!(
+            fee_tiers[fee_tiers.len() - 1] as u64 <= MAX_LIQUIDITY_FEE,
+            StateError::InvalidFee
+        );
+        require!(protocol_fee_percentage as u64 <= PERCENT_BASE, StateError::InvalidFee);
+
+        self.fee_tiers = fee_tiers;
+        self.protocol_fee_percentage = protocol_fee_percentage;
+        Ok(())
+    }
 }
 
 #[account]
@@ -34,148 +65,994 @@ pub struct Pool {
     /// Mint of token B
     pub mint_b: Pubkey,
 
+    /// Liquidity fee tier of the pool, part of its seeds: 10000 = 100%
+    pub liquidity_fee: u16,
+    /// Whether swaps charge a fee that follows recent volatility instead of `liquidity_fee`
+    pub dynamic_fee_enabled: bool,
+    /// Lower bound of the dynamic fee: 10000 = 100%
+    pub min_dynamic_fee: u16,
+    /// Upper bound of the dynamic fee: 10000 = 100%
+    pub max_dynamic_fee: u16,
+
+    /// Bonding curve used for swaps, liquidity and prices
+    pub curve_type: CurveType,
+    /// StableSwap amplification coefficient, unused by the constant product curve
+    pub amp: u64,
+
     /// 借贷池中token a的数量
     pub token_a_amount :u64,
     /// 借贷池中token b的数量
     pub token_b_amount :u64,
 
+    /// Protocol fees accrued in token A, held in the pool vault but not part of the reserves
+    pub protocol_fees_a: u64,
+    /// Protocol fees accrued in token B, held in the pool vault but not part of the reserves
+    pub protocol_fees_b: u64,
+
+    /// Time-weighted sum of the price of token A in token B (Q64.64)
+    pub price_a_cumulative: u128,
+    /// Time-weighted sum of the price of token B in token A (Q64.64)
+    pub price_b_cumulative: u128,
+    /// Slot at which the cumulative prices were last updated
+    pub last_price_update_slot: u64,
+    /// Number of slots the TWAP used for collateral valuation looks back
+    pub twap_window: u64,
+    /// Index of the most recent entry in `observations`
+    pub observation_index: u16,
+    /// Ring buffer of cumulative price snapshots
+    pub observations: [Observation; OBSERVATION_CAPACITY],
+
     // lending pool
-    /// 记录上次借贷池interest_step更新时的区块高度
-    pub borrow_interest_accumulator_block_height: u64,
-    /// 借款累计利息，随着区块高度增加而增加，但是增加幅度与资金借出量正相关
-    pub borrow_interest_accumulator: u64,
-    /// 共享借贷累加器区块高度
-    pub share_lending_block_height: u64,
-    /// 共享借贷累加器，资金借出量的时间积分
-    pub share_lending_accumulator: u64,
+    /// 出借 token A、以 token B 作为抵押品的借贷市场
+    pub lending_a: LendingMarket,
+    /// 出借 token B、以 token A 作为抵押品的借贷市场
+    pub lending_b: LendingMarket,
 }
 
 impl Pool {
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8;
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 2 + 1 + 2 + 2 + 1 + 8 + 8 + 8 + 8 + 8
+        + 16 + 16 + 8 + 8 + 2 + Observation::LEN * OBSERVATION_CAPACITY
+        + LendingMarket::LEN * 2;
 
-    // 计算 token A 的价值，返回token A等价于token B的数量
+    /// Swap reserves (A, B) every AMM instruction prices off. The vaults also hold the protocol fees
+    /// and whatever was sent to them directly, neither of which is part of the reserves.
+    pub fn reserves(&self) -> (u64, u64) {
+        (self.token_a_amount, self.token_b_amount)
+    }
+
+    /// Move the protocol's share of a swap fee, taken in token A if `fee_in_a`, out of the reserves
+    /// and into the protocol fee counters. Returns the protocol fee.
+    pub fn accrue_protocol_fee(
+        &mut self,
+        fee_in_a: bool,
+        fee_amount: u64,
+        protocol_fee_percentage: u16,
+    ) -> Result<u64> {
+        let protocol_fee = (fee_amount as u128)
+            .checked_mul(protocol_fee_percentage as u128)
+            .ok_or(StateError::CalculationError)?
+            .checked_div(PERCENT_BASE as u128)
+            .ok_or(StateError::CalculationError)? as u64;
+
+        let (reserve, fees) = if fee_in_a {
+            (&mut self.token_a_amount, &mut self.protocol_fees_a)
+        } else {
+            (&mut self.token_b_amount, &mut self.protocol_fees_b)
+        };
+        *reserve = reserve
+            .checked_sub(protocol_fee)
+            .ok_or(StateError::CalculationError)?;
+        *fees = fees
+            .checked_add(protocol_fee)
+            .ok_or(StateError::CalculationError)?;
+        Ok(protocol_fee)
+    }
+
+    /// Accumulate the spot price since the last update and record an observation.
+    /// Must be called before the reserves are changed.
     #[inline(never)]
-    pub fn calculate_token_a_value(&mut self, amount_a: u64) -> Result<u64> {
-        // 计算价值: value = amount_b * pool_a_amount / pool_b_amount
-        let token_a_value = (amount_a as u128)
-        .checked_mul(self.token_a_amount as u128)
+    pub fn update_price_accumulators(&mut self) -> Result<()> {
+        let current_slot = Clock::get()?.slot;
+
+        // Without liquidity there is no price, restart the history once liquidity arrives
+        if self.token_a_amount == 0 || self.token_b_amount == 0 {
+            self.last_price_update_slot = current_slot;
+            self.observation_index = 0;
+            self.observations = [Observation::default(); OBSERVATION_CAPACITY];
+            self.observations[0] = Observation {
+                slot: current_slot,
+                price_a_cumulative: self.price_a_cumulative,
+                price_b_cumulative: self.price_b_cumulative,
+            };
+            return Ok(());
+        }
+
+        let (price_a_cumulative, price_b_cumulative) = self.current_cumulative_prices(current_slot)?;
+        self.price_a_cumulative = price_a_cumulative;
+        self.price_b_cumulative = price_b_cumulative;
+        self.last_price_update_slot = current_slot;
+
+        // Space observations so that the ring buffer covers at least one TWAP window
+        let interval = self.twap_window.div_ceil(OBSERVATION_CAPACITY as u64 - 1).max(1);
+        let last_observation = self.observations[self.observation_index as usize];
+        if current_slot.saturating_sub(last_observation.slot) >= interval {
+            self.observation_index = ((self.observation_index as usize + 1) % OBSERVATION_CAPACITY) as u16;
+            self.observations[self.observation_index as usize] = Observation {
+                slot: current_slot,
+                price_a_cumulative,
+                price_b_cumulative,
+            };
+        }
+        Ok(())
+    }
+
+    /// Spot prices (price of A in B, price of B in A) as Q64.64 on the pool's curve
+    pub fn spot_prices(&self) -> Result<(u128, u128)> {
+        let price_a = curve::spot_price(self.curve_type, self.amp, self.token_a_amount, self.token_b_amount)?;
+        let price_b = curve::spot_price(self.curve_type, self.amp, self.token_b_amount, self.token_a_amount)?;
+        Ok((price_a, price_b))
+    }
+
+    /// Cumulative prices extrapolated to `current_slot` with the current spot price.
+    /// Accumulators are allowed to wrap, only differences between them are meaningful.
+    fn current_cumulative_prices(&self, current_slot: u64) -> Result<(u128, u128)> {
+        let slots_elapsed = current_slot
+            .checked_sub(self.last_price_update_slot)
+            .ok_or(StateError::CalculationError)?;
+        if slots_elapsed == 0 || self.token_a_amount == 0 || self.token_b_amount == 0 {
+            return Ok((self.price_a_cumulative, self.price_b_cumulative));
+        }
+        let (price_a, price_b) = self.spot_prices()?;
+        Ok((
+            self.price_a_cumulative.wrapping_add(price_a.wrapping_mul(slots_elapsed as u128)),
+            self.price_b_cumulative.wrapping_add(price_b.wrapping_mul(slots_elapsed as u128)),
+        ))
+    }
+
+    /// Time-weighted average prices (price of A in B, price of B in A) as Q64.64 over `twap_window`.
+    /// Uses the newest observation at least one window old. Fails while the history does not cover
+    /// a full window, such as right after the pool was created or its window was raised, since an
+    /// average over a few slots is easy to move.
+    #[inline(never)]
+    pub fn get_twap_prices(&self) -> Result<(u128, u128)> {
+        let current_slot = Clock::get()?.slot;
+        let target_slot = current_slot
+            .checked_sub(self.twap_window)
+            .ok_or(StateError::TwapUnavailable)?;
+
+        let observation = self.observations
+            .iter()
+            .filter(|o| o.slot != 0 && o.slot <= target_slot)
+            .max_by_key(|o| o.slot)
+            .copied()
+            .ok_or(StateError::TwapUnavailable)?;
+
+        let slots_elapsed = current_slot
+            .checked_sub(observation.slot)
+            .ok_or(StateError::CalculationError)?;
+        require!(slots_elapsed > 0, StateError::TwapUnavailable);
+
+        let (price_a_cumulative, price_b_cumulative) = self.current_cumulative_prices(current_slot)?;
+        Ok((
+            price_a_cumulative.wrapping_sub(observation.price_a_cumulative) / slots_elapsed as u128,
+            price_b_cumulative.wrapping_sub(observation.price_b_cumulative) / slots_elapsed as u128,
+        ))
+    }
+
+    /// Fee charged on swaps: 10000 = 100%.
+    /// In dynamic mode the fee grows with the deviation of the spot price from the TWAP,
+    /// starting at `min_dynamic_fee` and capped at `max_dynamic_fee`.
+    #[inline(never)]
+    pub fn swap_fee(&self) -> Result<u16> {
+        if !self.dynamic_fee_enabled {
+            return Ok(self.liquidity_fee);
+        }
+
+        // Without enough history fall back to the pool's fee tier
+        let twap_price_a = match self.get_twap_prices() {
+            Ok((price_a, _)) if price_a > 0 => price_a,
+            _ => {
+                return Ok(self.liquidity_fee.clamp(self.min_dynamic_fee, self.max_dynamic_fee));
+            }
+        };
+        let (spot_price_a, _) = self.spot_prices()?;
+
+        // Relative price movement over the TWAP window: 10000 = 100%
+        let deviation = mul_div_floor(
+            spot_price_a.abs_diff(twap_price_a),
+            PERCENT_BASE as u128,
+            twap_price_a,
+        )
         .ok_or(StateError::CalculationError)?;
+        let fee = (self.min_dynamic_fee as u128).saturating_add(
+            deviation.saturating_mul(DYNAMIC_FEE_SENSITIVITY as u128) / PERCENT_BASE as u128,
+        );
+        Ok(fee.min(self.max_dynamic_fee as u128) as u16)
+    }
+
+    // 按TWAP价格计算 token B 的价值，返回token B等价于token A的数量
+    #[inline(never)]
+    pub fn calculate_token_b_twap_value(&self, amount_b: u64) -> Result<u64> {
+        let (_, price_b) = self.get_twap_prices()?;
+        let token_b_value = (amount_b as u128)
+            .checked_mul(price_b)
+            .ok_or(StateError::CalculationError)?
+            >> 64;
+        if token_b_value > u64::MAX as u128 {
+            return Err(StateError::CalculationError1.into());
+        }
+        Ok(token_b_value as u64)
+    }
+
+    // 按TWAP价格计算 token A 的价值，返回token A等价于token B的数量
+    #[inline(never)]
+    pub fn calculate_token_a_twap_value(&self, amount_a: u64) -> Result<u64> {
+        let (price_a, _) = self.get_twap_prices()?;
+        let token_a_value = (amount_a as u128)
+            .checked_mul(price_a)
+            .ok_or(StateError::CalculationError)?
+            >> 64;
         if token_a_value > u64::MAX as u128 {
             return Err(StateError::CalculationError1.into());
         }
-        let res=token_a_value.checked_div(self.token_b_amount as u128)
-        .ok_or(StateError::CalculationError1)?;
-        Ok(res as u64)
+        Ok(token_a_value as u64)
     }
 
-    // 计算 token B 的价值，返回token B等价于token A的数量
+    // 按当前曲线上的现货价格计算 token A 的价值，返回token A等价于token B的数量
+    #[inline(never)]
+    pub fn calculate_token_a_value(&self, amount_a: u64) -> Result<u64> {
+        let (price_a, _) = self.spot_prices()?;
+        let token_a_value = (amount_a as u128)
+            .checked_mul(price_a)
+            .ok_or(StateError::CalculationError)?
+            >> 64;
+        if token_a_value > u64::MAX as u128 {
+            return Err(StateError::CalculationError1.into());
+        }
+        Ok(token_a_value as u64)
+    }
+
+    // 按当前曲线上的现货价格计算 token B 的价值，返回token B等价于token A的数量
     #[inline(never)]  // 强制不内联
-    pub fn calculate_token_b_value(
-        &mut self,
-        amount_b: u64,
-    ) -> Result<u64> {
-        // 计算价值: value = amount_b * pool_a_amount / pool_b_amount
+    pub fn calculate_token_b_value(&self, amount_b: u64) -> Result<u64> {
+        let (_, price_b) = self.spot_prices()?;
         let token_b_value = (amount_b as u128)
-        .checked_mul(self.token_a_amount as u128)
-        .ok_or(StateError::CalculationError)?;
+            .checked_mul(price_b)
+            .ok_or(StateError::CalculationError)?
+            >> 64;
         if token_b_value > u64::MAX as u128 {
             return Err(StateError::CalculationError1.into());
         }
-        let res=token_b_value.checked_div(self.token_b_amount as u128)
-        .ok_or(StateError::CalculationError1)?;
-        Ok(res as u64)
-    }   
+        Ok(token_b_value as u64)
+    }
 
-    // 更新借贷池的累计利息 （基于区块高度和基础利率和借出资金计算lendingpool的实际累积利息）
-    #[inline(never)]  // 强制不内联
-    pub fn update_borrow_interest_accumulator(&mut self,  current_borrowed: u64) -> Result<()> {
-        let current_block_height = Clock::get()?.slot;
-        let blocks_passed = calculate_blocks_passed(self.borrow_interest_accumulator_block_height, current_block_height)?;
-        
-        // todo: 计算interest_increase过程中的溢出问题
-        // 计算lending pool实际累积利息: 区块数* 借出资金数 * 基础利率  
-        let interest_increase = (blocks_passed as u128)
-            .checked_mul(current_borrowed as u128)
-            .ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_mul(BASE_INTEREST_RATE as u128)
-            .ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(PERCENT_BASE as u128)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
-        // 更新区块高度和累计利息
-        self.borrow_interest_accumulator_block_height = current_block_height;
-        if (self.borrow_interest_accumulator as u128).checked_add(interest_increase).ok_or(ProgramError::ArithmeticOverflow)? > u64::MAX as u128 {
-            self.borrow_interest_accumulator=u64::MAX;
-        }else{
-            self.borrow_interest_accumulator = self.borrow_interest_accumulator
-                .checked_add(interest_increase as u64)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
+    // 借贷方向: lend_mint 为 token A 时返回 true (出借 token A, 抵押 token B), 反之返回 false
+    pub fn is_lend_a(&self, lend_mint: &Pubkey, collateral_mint: &Pubkey) -> Result<bool> {
+        if *lend_mint == self.mint_a && *collateral_mint == self.mint_b {
+            Ok(true)
+        } else if *lend_mint == self.mint_b && *collateral_mint == self.mint_a {
+            Ok(false)
+        } else {
+            Err(StateError::InvalidLendingPair.into())
+        }
+    }
+
+    pub fn lending_market(&self, lend_a: bool) -> &LendingMarket {
+        if lend_a { &self.lending_a } else { &self.lending_b }
+    }
+
+    pub fn lending_market_mut(&mut self, lend_a: bool) -> &mut LendingMarket {
+        if lend_a { &mut self.lending_a } else { &mut self.lending_b }
+    }
+
+    // 借贷市场计息, 有利息时记录 InterestAccrued 事件. pool_key 为本池的地址
+    pub fn accrue_interest(&mut self, pool_key: Pubkey, lend_a: bool) -> Result<()> {
+        let lend_mint = if lend_a { self.mint_a } else { self.mint_b };
+        let lending_market = self.lending_market_mut(lend_a);
+        let interest = lending_market.accrue_interest()?;
+        if interest > 0 {
+            emit!(InterestAccrued {
+                pool: pool_key,
+                lend_mint,
+                interest,
+                borrow_index: lending_market.borrow_index,
+                supply_index: lending_market.supply_index,
+                total_borrows: lending_market.total_borrows,
+                total_deposits: lending_market.total_deposits,
+            });
         }
         Ok(())
     }
 
-    // 更新共享借贷累加器（共享借贷累加器是lender存入资金基于时间计算的积分）
-    #[inline(never)]  // 强制不内联
-    pub fn get_updated_share_lending_accumulator(&mut self, current_lending_receipt_amount: u64) -> Result<u64> {
-        let current_block_height = Clock::get()?.slot;
-    
-        // 计算区块增长数
-        let blocks_passed = calculate_blocks_passed(self.share_lending_block_height, current_block_height)?;
-
-        // todo: 计算 interest_lending_amount 过程中的溢出问题
-        // 计算lending pool实际累积资金时间成本: 区块数 * lending资金数
-        let interest_lending_amount = blocks_passed
-            .checked_mul(current_lending_receipt_amount)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
-        
-        // 更新区块高度和累计借出资金时间成本
-        self.share_lending_block_height = current_block_height;
-        if (self.share_lending_accumulator as u128).checked_add(interest_lending_amount as u128).ok_or(ProgramError::ArithmeticOverflow)? > u64::MAX as u128 {   
-            self.share_lending_accumulator=u64::MAX;
-        }else{
-            self.share_lending_accumulator = self.share_lending_accumulator
-                .checked_add(interest_lending_amount)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
-        }
-    
-        // 返回更新后的值
-        Ok(self.share_lending_accumulator)
+    // 借贷池中可借出/赎回的 lend token: 金库余额扣除另一方向借贷市场存入的抵押品
+    pub fn available_liquidity(&self, lend_a: bool, lending_pool_lend_token_amount: u64) -> u64 {
+        lending_pool_lend_token_amount.saturating_sub(self.lending_market(!lend_a).total_collateral)
+    }
+
+    // 按TWAP价格计算抵押品的价值，返回抵押品等价于 lend token 的数量
+    pub fn calculate_collateral_twap_value(&self, lend_a: bool, collateral_amount: u64) -> Result<u64> {
+        if lend_a {
+            self.calculate_token_b_twap_value(collateral_amount)
+        } else {
+            self.calculate_token_a_twap_value(collateral_amount)
+        }
+    }
+
+    // 按TWAP价格计算 lend token 的价值，返回 lend token 等价于抵押品的数量
+    pub fn calculate_lend_token_twap_value(&self, lend_a: bool, amount: u64) -> Result<u64> {
+        if lend_a {
+            self.calculate_token_a_twap_value(amount)
+        } else {
+            self.calculate_token_b_twap_value(amount)
+        }
+    }
+}
+
+
+/// 单方向的借贷市场: 出借池子中的一种代币，以另一种代币作为抵押品
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct LendingMarket {
+    /// 上次计息时的区块高度
+    pub last_accrual_slot: u64,
+    /// 借款指数 (WAD精度)，每次计息按借款利率单调增长
+    pub borrow_index: u128,
+    /// 存款指数 (WAD精度)，每次计息按存款获得的利息单调增长
+    pub supply_index: u128,
+    /// 借款总额 (lend token)，含已计入的利息
+    pub total_borrows: u64,
+    /// 存款总额 (lend token)，含已计入的利息
+    pub total_deposits: u64,
+    /// 抵押品总额 (collateral token)
+    pub total_collateral: u64,
+    /// 借款利率模型
+    pub interest_rate_model: InterestRateModel,
+    /// 最大借款价值比 (PERCENT_BASE)，借款后欠款不能超过抵押品价值 * max_ltv
+    pub max_ltv: u64,
+    /// 清算阈值 (PERCENT_BASE)，欠款超过抵押品价值 * liquidation_threshold 时可被清算
+    pub liquidation_threshold: u64,
+    /// 清算奖励 (PERCENT_BASE)，清算人获得的抵押品价值 = 还款价值 * (1 + liquidation_bonus)
+    pub liquidation_bonus: u64,
+    /// 未归还的闪电贷数量 (lend token)，为0表示没有进行中的闪电贷
+    pub flash_loan_amount: u64,
+}
+
+impl LendingMarket {
+    pub const LEN: usize = 8 + 16 + 16 + 8 + 8 + 8 + InterestRateModel::LEN + 8 + 8 + 8 + 8;
+
+    // 初始化借贷市场, 指数从 1.0 开始计息
+    pub fn init(&mut self, current_slot: u64, interest_rate_model: InterestRateModel) -> Result<()> {
+        interest_rate_model.validate()?;
+        self.last_accrual_slot = current_slot;
+        self.borrow_index = WAD;
+        self.supply_index = WAD;
+        self.interest_rate_model = interest_rate_model;
+        Ok(())
     }
 
+    // 计息: 按经过的区块数和借款利率增长借款指数，并将利息按比例计入存款指数, 返回本次利息
+    // 所有借贷指令在修改仓位之前都必须先调用 (通过 Pool::accrue_interest)
     #[inline(never)]  // 强制不内联
-    pub fn reduce_share_lending_accumulator(&mut self,  lender_redeem_lending_accumulator_amount: u64) -> Result<()> {
-        if (self.share_lending_accumulator as u128)<(lender_redeem_lending_accumulator_amount as u128) {
-            self.share_lending_accumulator=0;
-        }else{
-            self.share_lending_accumulator = self.share_lending_accumulator
-                .checked_sub(lender_redeem_lending_accumulator_amount)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
+    pub fn accrue_interest(&mut self) -> Result<u64> {
+        self.accrue_interest_at(Clock::get()?.slot)
+    }
+
+    // 计息到指定区块高度
+    fn accrue_interest_at(&mut self, current_block_height: u64) -> Result<u64> {
+        let blocks_passed = calculate_blocks_passed(self.last_accrual_slot, current_block_height)?;
+        if blocks_passed == 0 {
+            return Ok(0);
+        }
+        self.last_accrual_slot = current_block_height;
+        if self.total_borrows == 0 {
+            return Ok(0);
+        }
+
+        // 区间利率 (WAD): 区块数 * 按资金利用率计算的每区块利率
+        let interest_factor = self.interest_rate_model
+            .borrow_rate_per_slot(self.utilization()?)?
+            .checked_mul(blocks_passed as u128)
+            .ok_or(StateError::CalculationError)?;
+        // 区间利息: 借款总额 * 区间利率
+        let interest = (self.total_borrows as u128)
+            .checked_mul(interest_factor)
+            .ok_or(StateError::CalculationError)?
+            .checked_div(WAD)
+            .ok_or(StateError::CalculationError)?;
+
+        self.borrow_index = self.borrow_index
+            .checked_add(
+                self.borrow_index
+                    .checked_mul(interest_factor)
+                    .ok_or(StateError::CalculationError)?
+                    / WAD,
+            )
+            .ok_or(StateError::CalculationError)?;
+        if self.total_deposits > 0 {
+            self.supply_index = self.supply_index
+                .checked_add(
+                    self.supply_index
+                        .checked_mul(interest)
+                        .ok_or(StateError::CalculationError)?
+                        / self.total_deposits as u128,
+                )
+                .ok_or(StateError::CalculationError)?;
+        }
+
+        let interest = u64::try_from(interest).map_err(|_| StateError::CalculationError1)?;
+        self.total_borrows = self.total_borrows
+            .checked_add(interest)
+            .ok_or(StateError::CalculationError)?;
+        self.total_deposits = self.total_deposits
+            .checked_add(interest)
+            .ok_or(StateError::CalculationError)?;
+        Ok(interest)
+    }
+
+    // 闪电贷手续费: 按比例计入存款指数和存款总额, 全部归出借人所有
+    pub fn accrue_flash_loan_fee(&mut self, fee: u64) -> Result<()> {
+        require!(self.total_deposits > 0, StateError::CalculationError);
+        self.supply_index = self.supply_index
+            .checked_add(
+                self.supply_index
+                    .checked_mul(fee as u128)
+                    .ok_or(StateError::CalculationError)?
+                    / self.total_deposits as u128,
+            )
+            .ok_or(StateError::CalculationError)?;
+        self.total_deposits = self.total_deposits
+            .checked_add(fee)
+            .ok_or(StateError::CalculationError)?;
+        Ok(())
+    }
+
+    // 核销坏账: 从借款总额中扣除, 并按比例降低存款指数和存款总额, 由出借人分摊损失
+    pub fn write_off_bad_debt(&mut self, bad_debt: u64) -> Result<()> {
+        if bad_debt == 0 {
+            return Ok(());
+        }
+        self.total_borrows = self.total_borrows.saturating_sub(bad_debt);
+        if self.total_deposits == 0 {
+            return Ok(());
         }
+        let remaining_deposits = self.total_deposits.saturating_sub(bad_debt);
+        // 存款指数不能降为0, 否则之后按该指数记录的仓位无法折算
+        self.supply_index = mul_div_floor(
+            self.supply_index,
+            remaining_deposits as u128,
+            self.total_deposits as u128,
+        )
+        .ok_or(StateError::CalculationError)?
+        .max(1);
+        self.total_deposits = remaining_deposits;
+        Ok(())
+    }
 
+    // 资金利用率 (WAD): 借款总额 / 存款总额
+    pub fn utilization(&self) -> Result<u128> {
+        if self.total_deposits == 0 {
+            return Ok(0);
+        }
+        let utilization = (self.total_borrows as u128)
+            .checked_mul(WAD)
+            .ok_or(StateError::CalculationError)?
+            / self.total_deposits as u128;
+        Ok(utilization.min(WAD))
+    }
+
+    // 设置风险参数: 0 < max_ltv <= liquidation_threshold <= 100%,
+    // 且清算阈值加上清算奖励不超过100%, 保证清算时抵押品足以支付奖励
+    pub fn set_risk_params(
+        &mut self,
+        max_ltv: u64,
+        liquidation_threshold: u64,
+        liquidation_bonus: u64,
+    ) -> Result<()> {
+        require!(
+            max_ltv > 0 && max_ltv <= liquidation_threshold && liquidation_threshold <= PERCENT_BASE,
+            StateError::InvalidRiskParams
+        );
+        require!(liquidation_bonus <= MAX_LIQUIDATION_BONUS, StateError::InvalidRiskParams);
+        let threshold_with_bonus = (liquidation_threshold as u128)
+            * (PERCENT_BASE as u128 + liquidation_bonus as u128)
+            / PERCENT_BASE as u128;
+        require!(threshold_with_bonus <= PERCENT_BASE as u128, StateError::InvalidRiskParams);
+
+        self.max_ltv = max_ltv;
+        self.liquidation_threshold = liquidation_threshold;
+        self.liquidation_bonus = liquidation_bonus;
         Ok(())
     }
 
-    // 检查抵押率,返回是否满足抵押率,满足返回true,不满足返回false
+    // 检查借款价值比,欠款不超过抵押品价值 * max_ltv 返回true
     #[inline(never)]
-    pub fn check_collateral_ratio(&mut self, collateral_value_in_token_a: u64,borrow_amount: u64,
-    ) -> Result<bool> {
-        let required_collateral = (borrow_amount as u128)
-            .checked_mul(MIN_COLLATERAL_RATIO as u128)
+    pub fn check_max_ltv(&self, collateral_value: u64, debt: u64) -> Result<bool> {
+        check_collateral_factor(collateral_value, debt, self.max_ltv)
+    }
+
+    // 检查清算阈值,欠款不超过抵押品价值 * liquidation_threshold 返回true,否则仓位可被清算
+    #[inline(never)]
+    pub fn check_liquidation_threshold(&self, collateral_value: u64, debt: u64) -> Result<bool> {
+        check_collateral_factor(collateral_value, debt, self.liquidation_threshold)
+    }
+}
+
+
+/// Kinked borrow rate curve, all rates are per year with PERCENT_BASE = 100%
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct InterestRateModel {
+    /// Borrow rate at zero utilization
+    pub base_rate: u64,
+    /// Rate added between zero and optimal utilization
+    pub slope1: u64,
+    /// Utilization at which the second slope kicks in
+    pub optimal_utilization: u64,
+    /// Rate added between optimal and full utilization
+    pub slope2: u64,
+}
+
+impl InterestRateModel {
+    pub const LEN: usize = 8 + 8 + 8 + 8;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.optimal_utilization > 0 && self.optimal_utilization <= PERCENT_BASE,
+            StateError::InvalidInterestRateModel
+        );
+        let max_rate = self.base_rate
+            .checked_add(self.slope1)
+            .and_then(|rate| rate.checked_add(self.slope2))
+            .ok_or(StateError::InvalidInterestRateModel)?;
+        require!(max_rate <= MAX_BORROW_RATE, StateError::InvalidInterestRateModel);
+        Ok(())
+    }
+
+    // 年化借款利率 (PERCENT_BASE), utilization 为 WAD 精度
+    pub fn borrow_rate(&self, utilization: u128) -> Result<u64> {
+        let utilization = utilization
+            .checked_mul(PERCENT_BASE as u128)
             .ok_or(StateError::CalculationError)?
-            .checked_div(PERCENT_BASE as u128)
-            .ok_or(StateError::CalculationError)? as u64;
-        Ok(collateral_value_in_token_a >= required_collateral)
+            / WAD;
+        let optimal_utilization = self.optimal_utilization as u128;
+        let rate = if utilization <= optimal_utilization {
+            self.base_rate as u128 + self.slope1 as u128 * utilization / optimal_utilization
+        } else {
+            let excess_utilization = utilization - optimal_utilization;
+            let max_excess_utilization = PERCENT_BASE as u128 - optimal_utilization;
+            self.base_rate as u128
+                + self.slope1 as u128
+                + self.slope2 as u128 * excess_utilization / max_excess_utilization
+        };
+        Ok(rate as u64)
+    }
+
+    // 每区块借款利率 (WAD)
+    pub fn borrow_rate_per_slot(&self, utilization: u128) -> Result<u128> {
+        Ok((self.borrow_rate(utilization)? as u128)
+            .checked_mul(WAD)
+            .ok_or(StateError::CalculationError)?
+            / PERCENT_BASE as u128
+            / SLOTS_PER_YEAR as u128)
     }
 }
 
+#[account]
+#[derive(Default)]
+pub struct LendingPosition {
+    /// 所属的池子
+    pub pool: Pubkey,
+    /// lender
+    pub owner: Pubkey,
+    /// 出借的代币
+    pub lend_mint: Pubkey,
+    /// 按 entry_index 记录的存款价值 (lend token)
+    pub principal: u64,
+    /// 记录 principal 时的存款指数
+    pub entry_index: u128,
+}
+
+impl LendingPosition {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 16;
+
+    // 当前存款价值(含利息): principal * supply_index / entry_index, 向下取整
+    pub fn current_balance(&self, supply_index: u128) -> Result<u64> {
+        apply_index(self.principal, supply_index, self.entry_index, false)
+    }
+
+    // 按当前存款指数重新记录存款价值
+    pub fn rebase(&mut self, principal: u64, supply_index: u128) {
+        self.principal = principal;
+        self.entry_index = supply_index;
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct BorrowPosition {
+    /// 所属的池子
+    pub pool: Pubkey,
+    /// borrower
+    pub owner: Pubkey,
+    /// 借入的代币
+    pub lend_mint: Pubkey,
+    /// 按 entry_index 记录的欠款 (lend token)
+    pub principal: u64,
+    /// 抵押的 collateral token 数量
+    pub collateral: u64,
+    /// 记录 principal 时的借款指数
+    pub entry_index: u128,
+}
+
+impl BorrowPosition {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 16;
+
+    // 当前欠款(含利息): principal * borrow_index / entry_index, 向上取整
+    pub fn current_debt(&self, borrow_index: u128) -> Result<u64> {
+        apply_index(self.principal, borrow_index, self.entry_index, true)
+    }
+
+    // 按当前借款指数重新记录欠款
+    pub fn rebase(&mut self, principal: u64, borrow_index: u128) {
+        self.principal = principal;
+        self.entry_index = borrow_index;
+    }
+}
+
+/// Concentrated liquidity pool: liquidity is provided in tick ranges, coexisting with the full-range `Pool`
+#[account]
+pub struct ClPool {
+    /// Primary key of the AMM
+    pub amm: Pubkey,
+
+    /// Mint of token A
+    pub mint_a: Pubkey,
+
+    /// Mint of token B
+    pub mint_b: Pubkey,
+
+    /// Liquidity fee tier of the pool, part of its seeds: 10000 = 100%
+    pub liquidity_fee: u16,
+
+    /// Positions can only start and end on multiples of the tick spacing
+    pub tick_spacing: u16,
+
+    /// Square root of the price of A in B as Q64.64
+    pub sqrt_price: u128,
+
+    /// Tick of the current price: sqrt_price_at_tick(tick_current) <= sqrt_price
+    pub tick_current: i32,
+
+    /// Liquidity of the positions whose range contains the current price
+    pub liquidity: u128,
+
+    /// LP fees earned per unit of liquidity over the pool's life as Q64.64, allowed to wrap
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+
+    /// Protocol fees accrued, held in the pool vaults
+    pub protocol_fees_a: u64,
+    pub protocol_fees_b: u64,
+
+    /// One bit per usable tick (divided by the tick spacing) that has liquidity referencing it
+    pub tick_bitmap: [u64; TICK_BITMAP_WORDS],
+}
+
+impl ClPool {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 2 + 2 + 16 + 4 + 16 + 16 + 16 + 8 + 8
+        + 8 * TICK_BITMAP_WORDS;
+
+    const HALF_BITMAP: i32 = (TICK_BITMAP_WORDS * 64 / 2) as i32;
+
+    /// Lowest and highest ticks positions can use: MIN_TICK..MAX_TICK rounded inwards to the tick spacing
+    pub fn tick_range(&self) -> (i32, i32) {
+        let spacing = self.tick_spacing as i32;
+        (MIN_TICK / spacing * spacing, MAX_TICK / spacing * spacing)
+    }
+
+    /// Check a position range: aligned to the tick spacing and within the usable ticks
+    pub fn check_tick_range(&self, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        let spacing = self.tick_spacing as i32;
+        let (min_tick, max_tick) = self.tick_range();
+        require!(
+            tick_lower < tick_upper
+                && tick_lower >= min_tick
+                && tick_upper <= max_tick
+                && tick_lower % spacing == 0
+                && tick_upper % spacing == 0,
+            StateError::InvalidTickRange
+        );
+        Ok(())
+    }
+
+    fn bitmap_position(&self, tick: i32) -> usize {
+        (tick.div_euclid(self.tick_spacing as i32) + Self::HALF_BITMAP) as usize
+    }
+
+    /// Mark a usable tick as referenced by liquidity or not
+    pub fn flip_tick(&mut self, tick: i32, initialized: bool) {
+        let position = self.bitmap_position(tick);
+        let mask = 1u64 << (position % 64);
+        if initialized {
+            self.tick_bitmap[position / 64] |= mask;
+        } else {
+            self.tick_bitmap[position / 64] &= !mask;
+        }
+    }
+
+    /// Next tick a swap moves to from the current price: the closest initialized tick at or below
+    /// the current tick when selling A (above it when selling B), or the end of the usable range.
+    /// Returns the tick and whether it is initialized.
+    pub fn next_initialized_tick(&self, a_to_b: bool) -> (i32, bool) {
+        let spacing = self.tick_spacing as i32;
+        let (min_tick, max_tick) = self.tick_range();
+        let compressed = self.tick_current.div_euclid(spacing);
+
+        if a_to_b {
+            if compressed < -Self::HALF_BITMAP {
+                return (min_tick, false);
+            }
+            let start = ((compressed + Self::HALF_BITMAP) as usize).min(TICK_BITMAP_WORDS * 64 - 1);
+            let mut word = start / 64;
+            let bit = start % 64;
+            let mut bits = self.tick_bitmap[word] & (u64::MAX >> (63 - bit));
+            loop {
+                if bits != 0 {
+                    let position = word * 64 + 63 - bits.leading_zeros() as usize;
+                    return ((position as i32 - Self::HALF_BITMAP) * spacing, true);
+                }
+                if word == 0 {
+                    return (min_tick, false);
+                }
+                word -= 1;
+                bits = self.tick_bitmap[word];
+            }
+        } else {
+            let start = compressed + Self::HALF_BITMAP + 1;
+            if start >= 2 * Self::HALF_BITMAP {
+                return (max_tick, false);
+            }
+            let start = start.max(0) as usize;
+            let mut word = start / 64;
+            let bit = start % 64;
+            let mut bits = self.tick_bitmap[word] & (u64::MAX << bit);
+            loop {
+                if bits != 0 {
+                    let position = word * 64 + bits.trailing_zeros() as usize;
+                    return ((position as i32 - Self::HALF_BITMAP) * spacing, true);
+                }
+                if word == TICK_BITMAP_WORDS - 1 {
+                    return (max_tick, false);
+                }
+                word += 1;
+                bits = self.tick_bitmap[word];
+            }
+        }
+    }
+
+    /// LP fees earned per unit of liquidity inside [tick_lower, tick_upper) as Q64.64
+    pub fn fee_growth_inside(&self, lower: &ClTick, upper: &ClTick) -> (u128, u128) {
+        let (below_a, below_b) = if self.tick_current >= lower.tick_index {
+            (lower.fee_growth_outside_a, lower.fee_growth_outside_b)
+        } else {
+            (
+                self.fee_growth_global_a.wrapping_sub(lower.fee_growth_outside_a),
+                self.fee_growth_global_b.wrapping_sub(lower.fee_growth_outside_b),
+            )
+        };
+        let (above_a, above_b) = if self.tick_current < upper.tick_index {
+            (upper.fee_growth_outside_a, upper.fee_growth_outside_b)
+        } else {
+            (
+                self.fee_growth_global_a.wrapping_sub(upper.fee_growth_outside_a),
+                self.fee_growth_global_b.wrapping_sub(upper.fee_growth_outside_b),
+            )
+        };
+        (
+            self.fee_growth_global_a.wrapping_sub(below_a).wrapping_sub(above_a),
+            self.fee_growth_global_b.wrapping_sub(below_b).wrapping_sub(above_b),
+        )
+    }
+
+    /// Split a swap fee taken in token A if `fee_in_a` between the protocol and the in-range liquidity
+    pub fn accrue_fee(&mut self, fee_in_a: bool, fee_amount: u64, protocol_fee_percentage: u16) -> Result<()> {
+        let mut protocol_fee = (fee_amount as u128 * protocol_fee_percentage as u128 / PERCENT_BASE as u128) as u64;
+        let lp_fee = fee_amount - protocol_fee;
+        let fee_growth = match ((lp_fee as u128) << 64).checked_div(self.liquidity) {
+            Some(fee_growth) => fee_growth,
+            None => {
+                // Nobody to pay, the protocol keeps it
+                protocol_fee = fee_amount;
+                0
+            }
+        };
+        let (global, fees) = if fee_in_a {
+            (&mut self.fee_growth_global_a, &mut self.protocol_fees_a)
+        } else {
+            (&mut self.fee_growth_global_b, &mut self.protocol_fees_b)
+        };
+        *global = global.wrapping_add(fee_growth);
+        *fees = fees
+            .checked_add(protocol_fee)
+            .ok_or(StateError::CalculationError)?;
+        Ok(())
+    }
+
+    /// Add (or with a negative delta remove) liquidity that is active at the current price
+    pub fn apply_liquidity_delta(&mut self, liquidity_delta: i128) -> Result<()> {
+        self.liquidity = add_liquidity_delta(self.liquidity, liquidity_delta)?;
+        Ok(())
+    }
+}
+
+/// A tick referenced by concentrated liquidity positions
+#[account]
+#[derive(Default)]
+pub struct ClTick {
+    pub cl_pool: Pubkey,
+    pub tick_index: i32,
+
+    /// Liquidity of all positions using this tick as a bound
+    pub liquidity_gross: u128,
+    /// Liquidity added when the price crosses this tick upwards (removed downwards)
+    pub liquidity_net: i128,
+
+    /// Fee growth on the other side of this tick from the current price as Q64.64
+    pub fee_growth_outside_a: u128,
+    pub fee_growth_outside_b: u128,
+}
+
+impl ClTick {
+    pub const LEN: usize = 8 + 32 + 4 + 16 + 16 + 16 + 16;
+
+    /// Update the liquidity referencing this tick as the lower or upper bound of a position.
+    /// Returns whether the tick flipped between initialized and uninitialized.
+    pub fn update(
+        &mut self,
+        pool: &ClPool,
+        liquidity_delta: i128,
+        upper: bool,
+    ) -> Result<bool> {
+        let liquidity_gross_before = self.liquidity_gross;
+        self.liquidity_gross = add_liquidity_delta(liquidity_gross_before, liquidity_delta)?;
+
+        if liquidity_gross_before == 0 {
+            // By convention all growth before initialization happened below the tick
+            if self.tick_index <= pool.tick_current {
+                self.fee_growth_outside_a = pool.fee_growth_global_a;
+                self.fee_growth_outside_b = pool.fee_growth_global_b;
+            } else {
+                self.fee_growth_outside_a = 0;
+                self.fee_growth_outside_b = 0;
+            }
+        }
+
+        self.liquidity_net = if upper {
+            self.liquidity_net.checked_sub(liquidity_delta)
+        } else {
+            self.liquidity_net.checked_add(liquidity_delta)
+        }
+        .ok_or(StateError::CalculationError)?;
+
+        Ok((liquidity_gross_before == 0) != (self.liquidity_gross == 0))
+    }
+
+    /// Cross the tick during a swap, returns the liquidity net to apply when moving upwards
+    pub fn cross(&mut self, pool: &ClPool) -> i128 {
+        self.fee_growth_outside_a = pool.fee_growth_global_a.wrapping_sub(self.fee_growth_outside_a);
+        self.fee_growth_outside_b = pool.fee_growth_global_b.wrapping_sub(self.fee_growth_outside_b);
+        self.liquidity_net
+    }
+}
+
+/// Liquidity provided by `owner` between two ticks of a concentrated liquidity pool
+#[account]
+#[derive(Default)]
+pub struct ClPosition {
+    pub cl_pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+
+    /// Fee growth inside the range at the last update as Q64.64
+    pub fee_growth_inside_a_last: u128,
+    pub fee_growth_inside_b_last: u128,
+
+    /// Fees earned and not yet collected
+    pub tokens_owed_a: u64,
+    pub tokens_owed_b: u64,
+}
+
+impl ClPosition {
+    pub const LEN: usize = 8 + 32 + 32 + 4 + 4 + 16 + 16 + 16 + 8 + 8;
+
+    /// Credit the fees earned since the last update and apply a liquidity change
+    pub fn update(
+        &mut self,
+        liquidity_delta: i128,
+        fee_growth_inside_a: u128,
+        fee_growth_inside_b: u128,
+    ) -> Result<()> {
+        let owed_a = mul_div_floor(
+            fee_growth_inside_a.wrapping_sub(self.fee_growth_inside_a_last),
+            self.liquidity,
+            1 << 64,
+        )
+        .ok_or(StateError::CalculationError)?;
+        let owed_b = mul_div_floor(
+            fee_growth_inside_b.wrapping_sub(self.fee_growth_inside_b_last),
+            self.liquidity,
+            1 << 64,
+        )
+        .ok_or(StateError::CalculationError)?;
+
+        // Fees that do not fit are forfeited, as in Uniswap v3
+        self.tokens_owed_a = self.tokens_owed_a.saturating_add(owed_a.min(u64::MAX as u128) as u64);
+        self.tokens_owed_b = self.tokens_owed_b.saturating_add(owed_b.min(u64::MAX as u128) as u64);
+        self.fee_growth_inside_a_last = fee_growth_inside_a;
+        self.fee_growth_inside_b_last = fee_growth_inside_b;
+        self.liquidity = add_liquidity_delta(self.liquidity, liquidity_delta)?;
+        Ok(())
+    }
+}
+
+fn add_liquidity_delta(liquidity: u128, liquidity_delta: i128) -> Result<u128> {
+    if liquidity_delta >= 0 {
+        liquidity.checked_add(liquidity_delta as u128)
+    } else {
+        liquidity.checked_sub(liquidity_delta.unsigned_abs())
+    }
+    .ok_or(StateError::CalculationError.into())
+}
+
+// 欠款 <= 抵押品价值 * factor / PERCENT_BASE
+fn check_collateral_factor(collateral_value: u64, debt: u64, factor: u64) -> Result<bool> {
+    let debt_limit = (collateral_value as u128)
+        .checked_mul(factor as u128)
+        .ok_or(StateError::CalculationError)?
+        / PERCENT_BASE as u128;
+    Ok(debt as u128 <= debt_limit)
+}
+
+// 按指数变化折算金额: amount * current_index / entry_index
+#[inline(never)]
+pub fn apply_index(amount: u64, current_index: u128, entry_index: u128, round_up: bool) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+    let numerator = (amount as u128)
+        .checked_mul(current_index)
+        .ok_or(StateError::CalculationError)?;
+    let mut value = numerator
+        .checked_div(entry_index)
+        .ok_or(StateError::CalculationError)?;
+    if round_up && numerator % entry_index != 0 {
+        value += 1;
+    }
+    u64::try_from(value).map_err(|_| StateError::CalculationError1.into())
+}
 
 #[inline(never)]
-pub fn calculate_blocks_passed(borrow_interest_accumulator_block_height: u64, 
+pub fn calculate_blocks_passed(last_block_height: u64, 
     current_block_height: u64) -> Result<u64> {
     current_block_height
-        .checked_sub(borrow_interest_accumulator_block_height)
+        .checked_sub(last_block_height)
         .ok_or(ProgramError::ArithmeticOverflow.into())
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct Observation {
+    /// Slot at which the snapshot was taken
+    pub slot: u64,
+    /// `Pool::price_a_cumulative` at `slot`
+    pub price_a_cumulative: u128,
+    /// `Pool::price_b_cumulative` at `slot`
+    pub price_b_cumulative: u128,
+}
+
+impl Observation {
+    pub const LEN: usize = 8 + 16 + 16;
+}
+
 #[error_code]
 pub enum StateError {
     #[msg("Calculation error")]
@@ -184,4 +1061,124 @@ pub enum StateError {
     CalculationError1,
     #[msg("Invalid fee")]
     InvalidFee,
+    #[msg("Not enough price history for a TWAP")]
+    TwapUnavailable,
+    #[msg("Invalid interest rate model")]
+    InvalidInterestRateModel,
+    #[msg("Invalid risk parameters")]
+    InvalidRiskParams,
+    #[msg("Lend and collateral mints do not match the pool")]
+    InvalidLendingPair,
+    #[msg("Invalid tick range")]
+    InvalidTickRange,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> InterestRateModel {
+        InterestRateModel {
+            base_rate: 200,
+            slope1: 400,
+            optimal_utilization: 8000,
+            slope2: 6000,
+        }
+    }
+
+    fn market(total_deposits: u64, total_borrows: u64) -> LendingMarket {
+        let mut market = LendingMarket {
+            total_deposits,
+            total_borrows,
+            ..Default::default()
+        };
+        market.init(0, model()).unwrap();
+        market
+    }
+
+    #[test]
+    fn utilization_is_capped_between_zero_and_one() {
+        assert_eq!(market(0, 0).utilization().unwrap(), 0);
+        assert_eq!(market(1_000, 0).utilization().unwrap(), 0);
+        assert_eq!(market(1_000, 800).utilization().unwrap(), WAD * 8 / 10);
+        assert_eq!(market(1_000, 1_000).utilization().unwrap(), WAD);
+        // 坏账核销前借款可能超过存款, 利用率按100%计算
+        assert_eq!(market(1_000, 1_500).utilization().unwrap(), WAD);
+    }
+
+    #[test]
+    fn borrow_rate_follows_the_kinked_model() {
+        let model = model();
+        assert_eq!(model.borrow_rate(0).unwrap(), 200);
+        assert_eq!(model.borrow_rate(WAD * 4 / 10).unwrap(), 400);
+        assert_eq!(model.borrow_rate(WAD * 8 / 10).unwrap(), 600);
+        assert_eq!(model.borrow_rate(WAD * 9 / 10).unwrap(), 3600);
+        assert_eq!(model.borrow_rate(WAD).unwrap(), 6600);
+    }
+
+    #[test]
+    fn borrow_rate_is_monotonic_in_utilization() {
+        let model = model();
+        let mut previous = 0;
+        for step in 0..=100u128 {
+            let rate = model.borrow_rate(WAD * step / 100).unwrap();
+            assert!(rate >= previous);
+            previous = rate;
+        }
+        assert!(previous <= MAX_BORROW_RATE);
+    }
+
+    #[test]
+    fn accrue_interest_compounds_the_indexes() {
+        let mut market = market(1_000_000_000, 800_000_000);
+        let interest_factor = model().borrow_rate_per_slot(WAD * 8 / 10).unwrap() * 1_000;
+
+        let interest = market.accrue_interest_at(1_000).unwrap();
+        assert_eq!(interest as u128, 800_000_000 * interest_factor / WAD);
+        assert_eq!(market.borrow_index, WAD + interest_factor);
+        assert_eq!(market.total_borrows, 800_000_000 + interest);
+        assert_eq!(market.total_deposits, 1_000_000_000 + interest);
+        // 存款指数按利息占存款的比例增长
+        assert_eq!(market.supply_index, WAD + WAD * interest as u128 / 1_000_000_000);
+
+        // 第二次计息按已增长的指数复利
+        let borrow_index = market.borrow_index;
+        market.accrue_interest_at(2_000).unwrap();
+        assert!(market.borrow_index - borrow_index > borrow_index - WAD);
+        assert_eq!(market.last_accrual_slot, 2_000);
+    }
+
+    #[test]
+    fn accrue_interest_without_borrows_only_moves_the_slot() {
+        let mut market = market(1_000_000, 0);
+        assert_eq!(market.accrue_interest_at(500).unwrap(), 0);
+        assert_eq!(market.borrow_index, WAD);
+        assert_eq!(market.supply_index, WAD);
+        assert_eq!(market.last_accrual_slot, 500);
+        // 同一区块内重复计息不产生利息
+        assert_eq!(market.accrue_interest_at(500).unwrap(), 0);
+    }
+
+    #[test]
+    fn apply_index_rounds_in_the_protocols_favor() {
+        let entry_index = 3 * WAD;
+        let current_index = 4 * WAD;
+        // 10 * 4 / 3 = 13.33...
+        assert_eq!(apply_index(10, current_index, entry_index, false).unwrap(), 13);
+        assert_eq!(apply_index(10, current_index, entry_index, true).unwrap(), 14);
+        // 整除时不进位
+        assert_eq!(apply_index(9, current_index, entry_index, true).unwrap(), 12);
+        assert_eq!(apply_index(0, current_index, entry_index, true).unwrap(), 0);
+        assert!(apply_index(u64::MAX, 2 * WAD, WAD, false).is_err());
+    }
+
+    #[test]
+    fn tick_bitmap_covers_the_full_range_from_the_min_spacing() {
+        use crate::constants::MIN_TICK_SPACING;
+        let spacing = MIN_TICK_SPACING as i32;
+        assert!(-ClPool::HALF_BITMAP * spacing <= MIN_TICK);
+        assert!((ClPool::HALF_BITMAP - 1) * spacing >= MAX_TICK);
+        // One spacing less no longer covers it
+        assert!((ClPool::HALF_BITMAP - 1) * (spacing - 1) < MAX_TICK);
+    }
 }
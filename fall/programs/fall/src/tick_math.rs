@@ -0,0 +1,302 @@
+use anchor_lang::prelude::*;
+use crate::{
+    constants::{MAX_TICK, MIN_TICK, PERCENT_BASE},
+    math::{mul_div_ceil, mul_div_floor, U256},
+};
+
+/// 2^128 / sqrt(1.0001)^(2^i) as Q0.128, i = 0..18, each rounded down from the exact value
+/// (computed with 120 significant digits). Uniswap v3's TickMath rounds most of its factors up,
+/// so they differ from these by one in the last hex digit. Bits past 18 are not needed as
+/// |tick| <= MAX_TICK < 2^19.
+const SQRT_PRICE_FACTORS: [u128; 19] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e2139,
+    0xfff2e50f5f656932ef12357cf3c7fdcb,
+    0xffe5caca7e10e4e61c3624eaa0941ccf,
+    0xffcb9843d60f6159c9db58835c926643,
+    0xff973b41fa98c081472e6896dfb254bf,
+    0xff2ea16466c96a3843ec78b326b52860,
+    0xfe5dee046a99a2a811c461f1969c3052,
+    0xfcbe86c7900a88aedcffc83b479aa3a3,
+    0xf987a7253ac413176f2b074cf7815e53,
+    0xf3392b0822b70005940c7a398e4b70f2,
+    0xe7159475a2c29b7443b29c7fa6e889d8,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e4,
+    0x70d869a156d2a1b890bb3df62baf32f6,
+    0x31be135f97d08fd981231505542fcfa5,
+    0x9aa508b5b7a84e1c677de54f3e99bc8,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe97,
+];
+
+/// sqrt(1.0001^tick) as Q64.64, rounded up
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    require!((MIN_TICK..=MAX_TICK).contains(&tick), TickMathError::TickOutOfRange);
+
+    // Multiply the factors of the set bits of |tick| as Q128.128
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = U256::one() << 128;
+    for (i, factor) in SQRT_PRICE_FACTORS.iter().enumerate() {
+        if abs_tick & (1 << i) != 0 {
+            ratio = (ratio * U256::from(*factor)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Q128.128 to Q64.64
+    let sqrt_price = (ratio >> 64) + if ratio.low_u64() == 0 { 0 } else { 1 };
+    Ok(sqrt_price.as_u128())
+}
+
+/// Largest tick whose sqrt price is at most `sqrt_price`
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Result<i32> {
+    require!(
+        sqrt_price >= sqrt_price_at_tick(MIN_TICK)? && sqrt_price <= sqrt_price_at_tick(MAX_TICK)?,
+        TickMathError::SqrtPriceOutOfRange
+    );
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(mid)? <= sqrt_price {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+/// Token A for `liquidity` between two sqrt prices: L * (upper - lower) / (upper * lower)
+pub fn amount_a_delta(
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128> {
+    require!(sqrt_price_lower > 0, TickMathError::MathOverflow);
+    let numerator = (U256::from(liquidity) << 64)
+        .checked_mul(U256::from(sqrt_price_upper - sqrt_price_lower))
+        .ok_or(TickMathError::MathOverflow)?;
+    let amount = if round_up {
+        div_ceil(div_ceil(numerator, U256::from(sqrt_price_upper)), U256::from(sqrt_price_lower))
+    } else {
+        numerator / U256::from(sqrt_price_upper) / U256::from(sqrt_price_lower)
+    };
+    u128::try_from(amount).map_err(|_| TickMathError::MathOverflow.into())
+}
+
+/// Token B for `liquidity` between two sqrt prices: L * (upper - lower)
+pub fn amount_b_delta(
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128> {
+    let amount = if round_up {
+        mul_div_ceil(liquidity, sqrt_price_upper - sqrt_price_lower, 1 << 64)
+    } else {
+        mul_div_floor(liquidity, sqrt_price_upper - sqrt_price_lower, 1 << 64)
+    };
+    amount.ok_or(TickMathError::MathOverflow.into())
+}
+
+/// Sqrt price after adding `amount_in` of token A (price moves down) or token B (price moves up),
+/// rounded so the pool never gives out more than it receives
+pub fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_in: u64,
+    a_to_b: bool,
+) -> Result<u128> {
+    require!(liquidity > 0, TickMathError::MathOverflow);
+    if amount_in == 0 {
+        return Ok(sqrt_price);
+    }
+    let next = if a_to_b {
+        // L * P / (L + amount * P), rounded up
+        let numerator = U256::from(liquidity) << 64;
+        let product = U256::from(amount_in) * U256::from(sqrt_price);
+        match numerator.checked_mul(U256::from(sqrt_price)) {
+            Some(full) => div_ceil(full, numerator + product),
+            None => div_ceil(numerator, numerator / U256::from(sqrt_price) + U256::from(amount_in)),
+        }
+    } else {
+        // P + amount / L, rounded down
+        U256::from(sqrt_price) + (U256::from(amount_in) << 64) / U256::from(liquidity)
+    };
+    u128::try_from(next).map_err(|_| TickMathError::MathOverflow.into())
+}
+
+/// Result of swapping within a single liquidity range
+pub struct SwapStep {
+    pub sqrt_price_next: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// Swap up to `amount_remaining` (fee included) from `sqrt_price_current` towards
+/// `sqrt_price_target` with constant `liquidity`, taking the liquidity fee on the input
+pub fn compute_swap_step(
+    sqrt_price_current: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    liquidity_fee: u16,
+) -> Result<SwapStep> {
+    let a_to_b = sqrt_price_target <= sqrt_price_current;
+    let fee_base = PERCENT_BASE as u128 - liquidity_fee as u128;
+    let amount_remaining_less_fee = (amount_remaining as u128 * fee_base / PERCENT_BASE as u128) as u64;
+
+    let amount_in_to_target = if a_to_b {
+        amount_a_delta(sqrt_price_target, sqrt_price_current, liquidity, true)?
+    } else {
+        amount_b_delta(sqrt_price_current, sqrt_price_target, liquidity, true)?
+    };
+    let sqrt_price_next = if amount_remaining_less_fee as u128 >= amount_in_to_target {
+        sqrt_price_target
+    } else {
+        next_sqrt_price_from_input(sqrt_price_current, liquidity, amount_remaining_less_fee, a_to_b)?
+    };
+    let reached_target = sqrt_price_next == sqrt_price_target;
+
+    let (amount_in, amount_out) = if a_to_b {
+        let amount_in = if reached_target {
+            amount_in_to_target
+        } else {
+            amount_a_delta(sqrt_price_next, sqrt_price_current, liquidity, true)?
+        };
+        (amount_in, amount_b_delta(sqrt_price_next, sqrt_price_current, liquidity, false)?)
+    } else {
+        let amount_in = if reached_target {
+            amount_in_to_target
+        } else {
+            amount_b_delta(sqrt_price_current, sqrt_price_next, liquidity, true)?
+        };
+        (amount_in, amount_a_delta(sqrt_price_current, sqrt_price_next, liquidity, false)?)
+    };
+    let amount_in = u64::try_from(amount_in).map_err(|_| TickMathError::MathOverflow)?;
+    let amount_out = u64::try_from(amount_out).map_err(|_| TickMathError::MathOverflow)?;
+
+    // Whatever is left of the input when the target is not reached is the fee
+    let fee_amount = if reached_target {
+        (amount_in as u128 * liquidity_fee as u128).div_ceil(fee_base) as u64
+    } else {
+        amount_remaining - amount_in
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+fn div_ceil(numerator: U256, denominator: U256) -> U256 {
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    if remainder.is_zero() { quotient } else { quotient + 1 }
+}
+
+#[error_code]
+pub enum TickMathError {
+    #[msg("Tick out of range")]
+    TickOutOfRange,
+    #[msg("Sqrt price out of range")]
+    SqrtPriceOutOfRange,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICKS: [i32; 11] = [MIN_TICK, -300_000, -50_001, -1_000, -1, 0, 1, 999, 60_000, 250_007, MAX_TICK];
+
+    #[test]
+    fn sqrt_price_at_tick_zero_is_one() {
+        assert_eq!(sqrt_price_at_tick(0).unwrap(), 1 << 64);
+    }
+
+    #[test]
+    fn sqrt_price_at_tick_matches_the_float_formula() {
+        for tick in TICKS {
+            let expected = 1.0001f64.powf(tick as f64 / 2.0) * 2f64.powi(64);
+            let actual = sqrt_price_at_tick(tick).unwrap() as f64;
+            assert!((actual - expected).abs() / expected < 1e-9, "tick {tick}");
+        }
+    }
+
+    #[test]
+    fn sqrt_price_bounds_are_about_two_to_the_thirty_two() {
+        let min = sqrt_price_at_tick(MIN_TICK).unwrap();
+        let max = sqrt_price_at_tick(MAX_TICK).unwrap();
+        assert!(min > 1 << 32 && min < 1 << 33);
+        assert!(max > 1 << 95 && max <= 1 << 96);
+        assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+        assert!(tick_at_sqrt_price(min - 1).is_err());
+        assert!(tick_at_sqrt_price(max + 1).is_err());
+    }
+
+    #[test]
+    fn sqrt_price_is_strictly_increasing() {
+        let mut previous = sqrt_price_at_tick(MIN_TICK).unwrap();
+        for tick in (MIN_TICK + 1..=MAX_TICK).step_by(997).chain([MAX_TICK]) {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert!(sqrt_price > previous, "tick {tick}");
+            previous = sqrt_price;
+        }
+        for tick in -100..100 {
+            assert!(sqrt_price_at_tick(tick + 1).unwrap() > sqrt_price_at_tick(tick).unwrap());
+        }
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_round_trips() {
+        for tick in TICKS {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+            if tick < MAX_TICK {
+                // Any price below the next tick still maps to this one
+                let next = sqrt_price_at_tick(tick + 1).unwrap();
+                assert_eq!(tick_at_sqrt_price(next - 1).unwrap(), tick);
+            }
+        }
+    }
+
+    #[test]
+    fn amount_deltas_round_in_the_pools_favor() {
+        let lower = sqrt_price_at_tick(-1_000).unwrap();
+        let upper = sqrt_price_at_tick(1_000).unwrap();
+        for liquidity in [1u128, 1_000_003, 1 << 80] {
+            let a_down = amount_a_delta(lower, upper, liquidity, false).unwrap();
+            let a_up = amount_a_delta(lower, upper, liquidity, true).unwrap();
+            let b_down = amount_b_delta(lower, upper, liquidity, false).unwrap();
+            let b_up = amount_b_delta(lower, upper, liquidity, true).unwrap();
+            assert!(a_up >= a_down && a_up - a_down <= 1);
+            assert!(b_up >= b_down && b_up - b_down <= 1);
+        }
+    }
+
+    #[test]
+    fn swap_step_never_takes_more_than_the_remaining_input() {
+        let current = sqrt_price_at_tick(0).unwrap();
+        let liquidity = 1_000_000_000_000u128;
+        for (target_tick, amount_remaining) in [(-100, 1_000u64), (-100, 1_000_000_000), (100, 1_000), (100, 1_000_000_000)] {
+            let target = sqrt_price_at_tick(target_tick).unwrap();
+            let step = compute_swap_step(current, target, liquidity, amount_remaining, 30).unwrap();
+            assert!(step.amount_in + step.fee_amount <= amount_remaining);
+            assert!(step.amount_out <= step.amount_in);
+            if target_tick < 0 {
+                assert!(step.sqrt_price_next >= target && step.sqrt_price_next < current);
+            } else {
+                assert!(step.sqrt_price_next <= target && step.sqrt_price_next > current);
+            }
+        }
+    }
+}